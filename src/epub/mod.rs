@@ -0,0 +1,3 @@
+pub mod package_document;
+
+pub use package_document::*;
@@ -9,10 +9,18 @@ use xml::reader::XmlEvent;
 
 use failure::Error;
 use meta_data::Metadata;
-use manifest::Manifest;
+use manifest::{Item, Manifest};
 use spine::Spine;
 use failure::_core::convert::{TryFrom, TryInto};
 
+/// Escapes `&`, `<`, `>` and `"` for use in OPF text content or a quoted attribute.
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 #[derive(Debug)]
 pub struct PackageDocument {
     attributes: HashMap<OwnedName, String>,
@@ -70,11 +78,98 @@ impl PackageDocument {
             })?
             .to_string();
 
-        let meta_data = Metadata::new(package_element, &unique_identifier)?;
+        let child = |local_name: &str| package_element.children.iter()
+            .find(|e| match &e.event {
+                XmlEvent::StartElement { name, .. } => &name.local_name == local_name,
+                _ => false,
+            })
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: format!("<{}> element not found.", local_name)
+            });
+
+        let meta_data = Metadata::new(child("metadata")?, &unique_identifier)?;
+
+        let manifest = Manifest::new(child("manifest")?)?;
+
+        let spine = Spine::new(child("spine")?)?;
+
+        Ok(
+            Self {
+                attributes,
+                unique_identifier,
+                version,
+                meta_data,
+                manifest,
+                spine,
+            }
+        )
+    }
+
+    /// Like [`PackageDocument::new`], but never materializes the whole document
+    /// tree up front: `<package>`'s attributes are read from its `StartElement`
+    /// alone, and `<metadata>`/`<manifest>`/`<spine>` are each located and parsed
+    /// by a streaming query over the same event stream, analogous to an
+    /// event-driven reader. Memory stays bounded by the largest single element
+    /// rather than the whole package document.
+    pub fn new_streaming<R: Read>(source: R) -> Result<Self, Error> {
+        let parser = xml::EventReader::new(source);
+        let mut iter = parser.into_iter().peekable();
+
+        let package_attrs = loop {
+            match iter.peek() {
+                Some(Ok(XmlEvent::StartElement { name, .. })) if name.local_name == "package" => {
+                    match iter.next() {
+                        Some(Ok(XmlEvent::StartElement { attributes, .. })) => break attributes,
+                        _ => unreachable!(),
+                    }
+                }
+                Some(Ok(_)) => { let _ = iter.next(); }
+                _ => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Package element not found.".to_string()
+                }.into()),
+            }
+        };
+
+        let attributes = package_attrs.into_iter()
+            .map(|atr| (atr.name.clone(), atr.value.clone()))
+            .collect::<HashMap<OwnedName, String>>();
+
+        let unique_identifier = attributes.get(&OwnedName {
+            local_name: "unique-identifier".to_string(),
+            namespace: None,
+            prefix: None,
+        })
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "unique-identifier attribute is undefined.".to_string(),
+            })?
+            .to_string();
+
+        let version = attributes.get(&OwnedName {
+            local_name: "version".to_string(),
+            namespace: None,
+            prefix: None,
+        })
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "version attribute is undefined.".to_string(),
+            })?
+            .to_string();
 
-        let manifest = Manifest::new(package_element)?;
+        let metadata_elem = crate::util::find_by_name(&mut iter, "metadata")
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "Metadata element not found.".to_string()
+            })?;
+        let manifest_elem = crate::util::find_by_name(&mut iter, "manifest")
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "Manifest element not found.".to_string()
+            })?;
+        let spine_elem = crate::util::find_by_name(&mut iter, "spine")
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "Spine element not found.".to_string()
+            })?;
 
-        let spine = Spine::new(package_element)?;
+        let meta_data = Metadata::new(&metadata_elem, &unique_identifier)?;
+        let manifest = Manifest::new(&manifest_elem)?;
+        let spine = Spine::new(&spine_elem)?;
 
         Ok(
             Self {
@@ -88,6 +183,27 @@ impl PackageDocument {
         )
     }
 
+    /// The book's manifest items in reading order, per [`Spine::resolve_linear`].
+    pub fn reading_order(&self) -> Result<Vec<&Item>, Error> {
+        self.spine.resolve_linear(&self.manifest)
+    }
+
+    /// Serializes this package document back to a well-formed `<package>` OPF
+    /// document, reusing each section's own `to_opf_xml`.
+    pub fn to_opf_xml(&self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"{version}\" unique-identifier=\"{unique_identifier}\">\n\
+             {metadata}\n{manifest}\n{spine}\n\
+             </package>",
+            version = escape_xml(&self.version),
+            unique_identifier = escape_xml(&self.unique_identifier),
+            metadata = self.meta_data.to_opf_xml(),
+            manifest = self.manifest.to_opf_xml(),
+            spine = self.spine.to_opf_xml(),
+        )
+    }
+
     pub fn dir(&self) -> Option<Dir> {
         self.attributes.get(&OwnedName::local("dir"))
             .map(|s| match s.as_str() {
@@ -134,40 +250,21 @@ impl TryFrom<&str> for Dir {
     }
 }
 
+impl ToString for Dir {
+    fn to_string(&self) -> String {
+        format!("{:?}", &self)
+    }
+}
+
 trait Element {
     fn name() -> OwnedName;
-    fn from_xml_element<T, F>(value: &XmlElement, f: F) -> Option<T>
-        where F: FnOnce(&XmlElement, &Vec<OwnedAttribute>) -> T
-    {
-        match &value.event {
-            XmlEvent::StartElement {
-                name,
-                attributes, ..
-            } if name == &Self::name() => {
-                Some(f(value, attributes))
-            }
-            _ => None,
-        }
-    }
     fn id(attrs: &Vec<OwnedAttribute>) -> Option<String> {
         Self::get_attr(attrs, "id")
-        // attrs.iter()
-        //     .find_map(|a| {
-        //         if &a.name.local_name == "id" {
-        //             Some(a.value.to_string())
-        //         } else { None }
-        //     })
     }
     fn dir(attrs: &Vec<OwnedAttribute>) -> Option<Dir> {
         Self::get_attr(attrs, "dir")
             .map(|a| a.as_str().try_into().ok())
             .flatten()
-        // attrs.iter()
-        //     .find_map(|a|
-        //         if &a.name.local_name == "dir" {
-        //             a.value.as_str().try_into().ok()
-        //         } else { None }
-        //     )
     }
     fn xml_lang(attrs: &Vec<OwnedAttribute>) -> Option<String> {
         attrs.iter()
@@ -185,11 +282,34 @@ trait Element {
                 } else { None }
             })
     }
+    /// Matches `value` against this element's name and, on a match, threads its
+    /// attributes into `f` to build a [`FromXml`] impl.
+    fn from_xml_result<T, F>(value: &XmlElement, f: F) -> Result<T, EPUBError>
+        where F: FnOnce(&XmlElement, &Vec<OwnedAttribute>) -> Result<T, EPUBError>
+    {
+        match &value.event {
+            XmlEvent::StartElement {
+                name,
+                attributes, ..
+            } if name == &Self::name() => {
+                f(value, attributes)
+            }
+            _ => Err(EPUBError::PackageDocumentError {
+                err_msg: format!("Expected <{}> element.", Self::name().local_name)
+            }),
+        }
+    }
+    /// An attribute lookup that surfaces a descriptive [`EPUBError`] when absent,
+    /// for fields the element requires.
+    fn required_attr(attrs: &Vec<OwnedAttribute>, key: &str) -> Result<String, EPUBError> {
+        Self::get_attr(attrs, key).ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("`{}` attribute is undefined on <{}>.", key, Self::name().local_name)
+        })
+    }
 }
 
 pub mod meta_data {
     use super::*;
-    use failure::_core::convert::TryFrom;
 
     use failure::_core::str::FromStr;
 
@@ -208,37 +328,28 @@ pub mod meta_data {
         attributes: HashMap<OwnedName, String>,
         optionals: Vec<OptionalElement>,
         meta: Vec<MetaElem>,
+        creators: Vec<Creator>,
+        contributors: Vec<Creator>,
+        collection: Option<Collection>,
     }
 
     impl Metadata {
-        pub fn new(package_element: &XmlElement, unique_identifier: &str) -> Result<Self, Error> {
-            let (meta_data_elem, attributes) = package_element.children.iter()
-                .find_map(|e| match &e.event {
-                    XmlEvent::StartElement {
-                        name,
-                        attributes, ..
-                    } => if &name.local_name == "metadata" {
-                        Some((e, attributes))
-                    } else {
-                        None
-                    }
-                    _ => None
-                })
-                .ok_or(EPUBError::PackageDocumentError {
-                    err_msg: "Metadata element not found.".to_string()
-                })?;
+        /// Builds a `Metadata` directly from an already-located `<metadata>` element.
+        pub fn new(meta_data_elem: &XmlElement, unique_identifier: &str) -> Result<Self, Error> {
+            let attributes = match &meta_data_elem.event {
+                XmlEvent::StartElement { attributes, .. } => attributes,
+                _ => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Expected <metadata> element.".to_string()
+                }.into()),
+            };
 
             let attributes = attributes.into_iter()
                 .map(|atr| (atr.name.clone(), atr.value.clone()))
                 .collect::<HashMap<OwnedName, String>>();
 
-            let meta = meta_data_elem.children.iter()
-                .flat_map(|e| MetaElem::try_from(e))
-                .collect::<Vec<MetaElem>>();
+            let meta = meta_data_elem.parse_children::<MetaElem>();
 
-            let identifier = meta_data_elem.children.iter()
-                .flat_map(|e| Identifier::try_from(e))
-                .collect::<Vec<Identifier>>();
+            let identifier = meta_data_elem.parse_children::<Identifier>();
 
             let unique_identifier = identifier.iter()
                 .find_map(|id| if &id.id == &Some(unique_identifier.to_string()) {
@@ -248,27 +359,25 @@ pub mod meta_data {
                     err_msg: "Unique identifier element not found.".to_string()
                 })?;
 
-            let titles = meta_data_elem.children.iter()
-                .flat_map(|e| Title::try_from(e))
-                .collect::<Vec<Title>>();
+            let titles = meta_data_elem.parse_children::<Title>();
             // title要素の有無を確認する
             titles.get(0)
                 .ok_or(EPUBError::PackageDocumentError {
                     err_msg: "Title not found.".to_string()
                 })?;
 
-            let languages = meta_data_elem.children.iter()
-                .flat_map(|e| Language::try_from(e))
-                .collect::<Vec<Language>>();
+            let languages = meta_data_elem.parse_children::<Language>();
             // language要素の有無を確認する
             languages.get(0)
                 .ok_or(EPUBError::PackageDocumentError {
                     err_msg: "Language not found.".to_string()
                 })?;
 
-            let optionals = meta_data_elem.children.iter()
-                .flat_map(|e| OptionalElement::try_from(e))
-                .collect();
+            let optionals: Vec<OptionalElement> = meta_data_elem.parse_children::<OptionalElement>();
+
+            let creators = Self::build_creators(&optionals, &meta, OptionalElementName::creator);
+            let contributors = Self::build_creators(&optionals, &meta, OptionalElementName::contributor);
+            let collection = Self::build_collection(&meta);
 
             Ok(Self {
                 unique_identifier,
@@ -278,6 +387,51 @@ pub mod meta_data {
                 languages,
                 optionals,
                 meta,
+                creators,
+                contributors,
+                collection,
+            })
+        }
+
+        /// `<dc:creator>`/`<dc:contributor>` entries refined with an `opf:role` and
+        /// an `opf:file-as` sort name, matched up via the `refines="#id"` convention.
+        fn build_creators(optionals: &[OptionalElement], meta: &[MetaElem], name: OptionalElementName) -> Vec<Creator> {
+            optionals.iter()
+                .filter(|o| o.name == name)
+                .map(|o| {
+                    let refines = o.id.as_ref().map(|id| format!("#{}", id));
+                    let refinement = |property: MetaDataProperty| -> Option<String> {
+                        let refines = refines.as_ref()?;
+                        meta.iter()
+                            .find(|m| m.refines.as_ref() == Some(refines) && m.property == property)
+                            .map(|m| m.value.clone())
+                    };
+
+                    Creator {
+                        name: o.value.clone(),
+                        file_as: refinement(MetaDataProperty::file_as),
+                        role: refinement(MetaDataProperty::role),
+                    }
+                })
+                .collect()
+        }
+
+        /// The `belongs-to-collection` refinement and its `group-position` index,
+        /// used by series metadata.
+        fn build_collection(meta: &[MetaElem]) -> Option<Collection> {
+            let collection_meta = meta.iter()
+                .find(|m| m.property == MetaDataProperty::belongs_to_collection)?;
+
+            let refines = collection_meta.id.as_ref().map(|id| format!("#{}", id));
+            let index = refines
+                .as_ref()
+                .and_then(|refines| meta.iter()
+                    .find(|m| m.refines.as_ref() == Some(refines) && m.property == MetaDataProperty::group_position))
+                .and_then(|m| m.value.parse::<u32>().ok());
+
+            Some(Collection {
+                name: collection_meta.value.clone(),
+                index,
             })
         }
 
@@ -300,6 +454,127 @@ pub mod meta_data {
         pub fn language(&self) -> Option<&Language> { self.languages.get(0) }
 
         pub fn languages(&self) -> &Vec<Language> { &self.languages }
+
+        pub fn creators(&self) -> &Vec<Creator> { &self.creators }
+
+        pub fn contributors(&self) -> &Vec<Creator> { &self.contributors }
+
+        pub fn subjects(&self) -> Vec<&str> {
+            self.optionals_by_name(OptionalElementName::subject)
+        }
+
+        pub fn date(&self) -> Option<&str> {
+            self.optionals_by_name(OptionalElementName::date).into_iter().next()
+        }
+
+        pub fn publisher(&self) -> Option<&str> {
+            self.optionals_by_name(OptionalElementName::publisher).into_iter().next()
+        }
+
+        pub fn collection(&self) -> Option<&Collection> { self.collection.as_ref() }
+
+        fn optionals_by_name(&self, name: OptionalElementName) -> Vec<&str> {
+            self.optionals.iter()
+                .filter(|o| o.name == name)
+                .map(|o| o.value.as_str())
+                .collect()
+        }
+
+        /// Serializes this metadata back to a full `<metadata>` OPF element.
+        /// Creators/contributors are given a synthetic `id` so their `file-as`/
+        /// `role` can round-trip as `<meta refines>` elements, same as on read;
+        /// the remaining optional `dc:*` elements (subject, date, publisher,
+        /// rights, etc.) are written back out as-is via [`OptionalElement::to_opf_xml`].
+        pub fn to_opf_xml(&self) -> String {
+            let mut body = String::new();
+
+            for identifier in &self.identifier {
+                body += &identifier.to_opf_xml();
+            }
+
+            for title in &self.titles {
+                body += &title.to_opf_xml();
+            }
+
+            for language in &self.languages {
+                body += &format!("<dc:language>{}</dc:language>", escape_xml(&language.language));
+            }
+
+            for (i, creator) in self.creators.iter().enumerate() {
+                body += &creator.to_opf_xml("dc:creator", &format!("creator{:02}", i + 1));
+            }
+
+            for (i, contributor) in self.contributors.iter().enumerate() {
+                body += &contributor.to_opf_xml("dc:contributor", &format!("contributor{:02}", i + 1));
+            }
+
+            for optional in &self.optionals {
+                if optional.name != OptionalElementName::creator && optional.name != OptionalElementName::contributor {
+                    body += &optional.to_opf_xml();
+                }
+            }
+
+            if let Some(collection) = &self.collection {
+                body += &collection.to_opf_xml();
+            }
+
+            format!(
+                "<metadata xmlns=\"http://www.idpf.org/2007/opf\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:opf=\"http://www.idpf.org/2007/opf\">{}</metadata>",
+                body,
+            )
+        }
+    }
+
+    /// A `<dc:creator>` or `<dc:contributor>` entry, together with the `opf:role`
+    /// (e.g. `aut`, `edt`, `ill`) and `opf:file-as` sort name refining it.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct Creator {
+        pub name: String,
+        pub file_as: Option<String>,
+        pub role: Option<String>,
+    }
+
+    impl Creator {
+        /// Serializes this entry as a `tag` element (`dc:creator`/`dc:contributor`)
+        /// with `id`, plus a `<meta refines="#id">` for each of `file_as`/`role`.
+        fn to_opf_xml(&self, tag: &str, id: &str) -> String {
+            let mut xml = format!(r#"<{tag} id="{id}">{name}</{tag}>"#, tag = tag, id = id, name = escape_xml(&self.name));
+
+            if let Some(file_as) = &self.file_as {
+                xml += &format!(r##"<meta refines="#{id}" property="file-as">{value}</meta>"##, id = id, value = escape_xml(file_as));
+            }
+            if let Some(role) = &self.role {
+                xml += &format!(r##"<meta refines="#{id}" property="role">{value}</meta>"##, id = id, value = escape_xml(role));
+            }
+
+            xml
+        }
+    }
+
+    /// The `belongs-to-collection` refinement: a series or collection name plus
+    /// its `group-position` index within that series.
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct Collection {
+        pub name: String,
+        pub index: Option<u32>,
+    }
+
+    impl Collection {
+        /// Serializes this collection as a `belongs-to-collection` `<meta>`, plus a
+        /// `group-position` `<meta refines>` if an index was recorded.
+        fn to_opf_xml(&self) -> String {
+            let id = "collection";
+            let mut xml = format!(
+                r#"<meta id="{id}" property="belongs-to-collection">{name}</meta>"#,
+                id = id, name = escape_xml(&self.name),
+            );
+
+            if let Some(index) = self.index {
+                xml += &format!(r##"<meta refines="#{id}" property="group-position">{index}</meta>"##, id = id, index = index);
+            }
+
+            xml
+        }
     }
 
     #[derive(Debug, Clone, Eq, PartialEq)]
@@ -318,27 +593,27 @@ pub mod meta_data {
             OwnedName {
                 prefix: None,
                 local_name: "meta".into(),
-                namespace: Some(String::from("http://purl.org/dc/elements/1.1/")),
+                namespace: Some(String::from("http://www.idpf.org/2007/opf")),
             }
         }
     }
 
-    impl TryFrom<&XmlElement> for MetaElem {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            Self::from_xml_element(value, |elem, attrs| {
+    impl FromXml for MetaElem {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            Self::from_xml_result(value, |elem, attrs| {
                 let value = elem.inner_text();
                 let dir = Self::dir(attrs);
                 let id = Self::id(attrs);
-                let property: MetaDataProperty = Self::get_attr(attrs, "property")
-                    .map(|s| MetaDataProperty::from_str(&s).ok())
-                    .flatten()?;
+                let property_str = Self::required_attr(attrs, "property")?;
+                let property = MetaDataProperty::from_str(&property_str)
+                    .map_err(|_| EPUBError::PackageDocumentError {
+                        err_msg: format!("Unknown meta property: {}", property_str)
+                    })?;
                 let refines = Self::get_attr(attrs, "refines");
                 let scheme = Self::get_attr(attrs, "scheme");
                 let xml_lang = Self::get_attr(attrs, "xml:lang");
 
-                Some(MetaElem {
+                Ok(MetaElem {
                     value,
                     dir,
                     id,
@@ -348,8 +623,6 @@ pub mod meta_data {
                     xml_lang
                 })
             })
-                .ok_or(())?
-                .ok_or(())
         }
     }
 
@@ -419,17 +692,23 @@ pub mod meta_data {
         }
     }
 
-    impl TryFrom<&XmlElement> for Identifier {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            Self::from_xml_element(value, |elem, attrs| {
+    impl FromXml for Identifier {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            Self::from_xml_result(value, |elem, attrs| {
                 let identifier = elem.inner_text();
                 let id = Self::id(attrs);
 
-                Identifier { id, identifier }
+                Ok(Identifier { id, identifier })
             })
-                .ok_or(())
+        }
+    }
+
+    impl Identifier {
+        fn to_opf_xml(&self) -> String {
+            let id_attr = self.id.as_ref()
+                .map(|id| format!(r#" id="{}""#, escape_xml(id)))
+                .unwrap_or_default();
+            format!("<dc:identifier{}>{}</dc:identifier>", id_attr, escape_xml(&self.identifier))
         }
     }
 
@@ -452,19 +731,31 @@ pub mod meta_data {
         }
     }
 
-    impl TryFrom<&XmlElement> for Title {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            Self::from_xml_element(value, |elem, attrs| {
+    impl FromXml for Title {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            Self::from_xml_result(value, |elem, attrs| {
                 let title = elem.inner_text();
                 let dir = Self::dir(attrs);
                 let id = Self::id(attrs);
                 let xml_lang = Self::xml_lang(attrs);
 
-                Title { title, dir, id, xml_lang }
+                Ok(Title { title, dir, id, xml_lang })
             })
-                .ok_or(())
+        }
+    }
+
+    impl Title {
+        fn to_opf_xml(&self) -> String {
+            let id_attr = self.id.as_ref()
+                .map(|id| format!(r#" id="{}""#, escape_xml(id)))
+                .unwrap_or_default();
+            let dir_attr = self.dir.as_ref()
+                .map(|dir| format!(r#" dir="{}""#, dir.to_string()))
+                .unwrap_or_default();
+            let xml_lang_attr = self.xml_lang.as_ref()
+                .map(|lang| format!(r#" xml:lang="{}""#, escape_xml(lang)))
+                .unwrap_or_default();
+            format!("<dc:title{}{}{}>{}</dc:title>", id_attr, dir_attr, xml_lang_attr, escape_xml(&self.title))
         }
     }
 
@@ -486,17 +777,14 @@ pub mod meta_data {
         }
     }
 
-    impl TryFrom<&XmlElement> for Language {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            Self::from_xml_element(value, |elem, attrs| {
+    impl FromXml for Language {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            Self::from_xml_result(value, |elem, attrs| {
                 let language = elem.inner_text();
                 let id = Self::id(attrs);
 
-                Language { language, id }
+                Ok(Language { language, id })
             })
-                .ok_or(())
         }
     }
 
@@ -559,21 +847,20 @@ pub mod meta_data {
         xml_lang: Option<String>,
     }
 
-    impl TryFrom<&XmlElement> for OptionalElement {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
+    impl FromXml for OptionalElement {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
             match &value.event {
                 XmlEvent::StartElement {
                     name,
                     attributes, ..
                 } if name.prefix == Some(String::from("dc"))
                     && name.namespace == Some(String::from("http://purl.org/dc/elements/1.1/"))
-                => if let Ok(name) = OptionalElementName::from_str(&name.local_name) {
+                => {
+                    let name = OptionalElementName::from_str(&name.local_name)
+                        .map_err(|_| EPUBError::PackageDocumentError {
+                            err_msg: format!("Unsupported dc element: {}", &name.local_name)
+                        })?;
                     let value = value.inner_text();
-                    // let dir = Element::dir(attributes);
-                    // let id =  Element::id(attributes);
-                    // let xml_lang = Element::xml_lang(attributes);
                     let dir = Identifier::dir(attributes);
                     let id = Identifier::id(attributes);
                     let xml_lang = Identifier::xml_lang(attributes);
@@ -585,14 +872,31 @@ pub mod meta_data {
                         id,
                         xml_lang,
                     })
-                } else {
-                    Err(())
                 }
-                _ => Err(())
+                _ => Err(EPUBError::PackageDocumentError {
+                    err_msg: "Expected a <dc:*> optional metadata element.".to_string()
+                })
             }
         }
     }
 
+    impl OptionalElement {
+        fn to_opf_xml(&self) -> String {
+            let tag = format!("dc:{}", self.name.to_string());
+            let id_attr = self.id.as_ref()
+                .map(|id| format!(r#" id="{}""#, escape_xml(id)))
+                .unwrap_or_default();
+            let dir_attr = self.dir.as_ref()
+                .map(|dir| format!(r#" dir="{}""#, dir.to_string()))
+                .unwrap_or_default();
+            let xml_lang_attr = self.xml_lang.as_ref()
+                .map(|lang| format!(r#" xml:lang="{}""#, escape_xml(lang)))
+                .unwrap_or_default();
+            format!("<{tag}{id}{dir}{lang}>{value}</{tag}>",
+                    tag = tag, id = id_attr, dir = dir_attr, lang = xml_lang_attr, value = escape_xml(&self.value))
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -727,6 +1031,43 @@ pub mod meta_data {
 
             Ok(())
         }
+
+        /// [`Metadata::to_opf_xml`], reparsed via [`Metadata::new`], must preserve
+        /// the identifier, title, language, and a refined `dc:creator`.
+        #[test]
+        fn round_trips_through_opf_xml() -> Result<(), Error> {
+            let source = r##"<metadata xmlns="http://www.idpf.org/2007/opf" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+<dc:identifier id="bookid">urn:uuid:test</dc:identifier>
+<dc:title>Test Book</dc:title>
+<dc:language>en</dc:language>
+<dc:creator id="creator01">Jane Doe</dc:creator>
+<meta refines="#creator01" property="role" scheme="marc:relators">aut</meta>
+</metadata>"##;
+
+            let metadata = Metadata::new(&parse_opf_element(source), "bookid")?;
+            let round_tripped = Metadata::new(&parse_opf_element(&metadata.to_opf_xml()), "bookid")?;
+
+            assert_eq!(metadata.unique_identifier(), round_tripped.unique_identifier());
+            assert_eq!(metadata.title(), round_tripped.title());
+            assert_eq!(metadata.language(), round_tripped.language());
+            assert_eq!(metadata.creators(), round_tripped.creators());
+            assert_eq!(round_tripped.creators()[0].role, Some("aut".to_string()));
+
+            Ok(())
+        }
+
+        /// Parses a single top-level OPF element (e.g. the `<metadata>` produced by
+        /// [`Metadata::to_opf_xml`]) back into an [`XmlElement`], for round-trip tests.
+        fn parse_opf_element(xml: &str) -> XmlElement {
+            let parser = xml::EventReader::new(std::io::Cursor::new(xml.as_bytes().to_vec()));
+            let tree = Xml::new(&mut parser.into_iter().peekable());
+            tree.vec.into_iter().next()
+                .and_then(|doc| doc.children.into_iter().find(|e| match e.event {
+                    XmlEvent::StartElement { .. } => true,
+                    _ => false,
+                }))
+                .expect("parsed element")
+        }
     }
 }
 
@@ -734,41 +1075,34 @@ pub mod manifest {
     use super::*;
     use std::collections::HashSet;
     use failure::_core::str::FromStr;
+    use crate::media_type::MediaType;
 
     ///! レンディションを構成する出版物リソースの完全なリスト
     #[derive(Debug, Eq, PartialEq)]
     pub struct Manifest {
         id: Option<String>,
-        items: HashSet<Item>,
+        pub(crate) items: HashSet<Item>,
         cover_image: Option<Item>,
-        nav: Item,
+        pub(crate) nav: Item,
     }
 
     impl Manifest {
-        pub fn new(package_element: &XmlElement) -> Result<Self, Error> {
-            let (manifest_elem, attributes) = package_element.children.iter()
-                .find_map(|e| match &e.event {
-                    XmlEvent::StartElement {
-                        name,
-                        attributes, ..
-                    } => if &name.local_name == "manifest" {
-                        Some((e, attributes))
-                    } else {
-                        None
-                    }
-                    _ => None
-                })
-                .ok_or(EPUBError::PackageDocumentError {
-                    err_msg: "Manifest element not found.".to_string()
-                })?;
+        /// Builds a `Manifest` directly from an already-located `<manifest>` element.
+        pub fn new(manifest_elem: &XmlElement) -> Result<Self, Error> {
+            let attributes = match &manifest_elem.event {
+                XmlEvent::StartElement { attributes, .. } => attributes,
+                _ => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Expected <manifest> element.".to_string()
+                }.into()),
+            };
 
             let id = attributes.iter()
                 .find_map(|a| if &a.name.local_name == "id" {
                     Some(a.value.to_string())
                 } else { None });
 
-            let items: HashSet<Item> = manifest_elem.children.iter()
-                .flat_map(|e| Item::try_from(e))
+            let items: HashSet<Item> = manifest_elem.parse_children::<Item>()
+                .into_iter()
                 .collect();
 
             let cover_image = items.iter()
@@ -782,45 +1116,142 @@ pub mod manifest {
                 })?
                 .clone();
 
-            // check fallback chain
-            let map = items.iter()
+            check_fallback_chains(&items)?;
+
+            Ok(Self { id, items, cover_image, nav })
+        }
+
+        /// Starts assembling a `Manifest` in memory, e.g. for writing a new EPUB.
+        pub fn builder() -> ManifestBuilder {
+            ManifestBuilder::default()
+        }
+
+        /// Serializes this manifest back to a full `<manifest>` OPF element.
+        pub fn to_opf_xml(&self) -> String {
+            let items: String = self.items.iter().map(Item::to_opf_xml).collect();
+            let id_attr = self.id.as_ref()
+                .map(|id| format!(r#" id="{}""#, escape_xml(id)))
+                .unwrap_or_default();
+            format!("<manifest xmlns=\"http://www.idpf.org/2007/opf\"{}>{}</manifest>", id_attr, items)
+        }
+
+        /// Finds the `<item>` a reading system that only understands `supported`
+        /// media types should actually render for `id`: the item itself if its
+        /// media type is supported (or a core media type), otherwise the first
+        /// item reached by following its `fallback` chain that qualifies. Returns
+        /// `None` if `id` doesn't resolve or the chain is exhausted without
+        /// finding a usable item, guarding against circular references the same
+        /// way [`check_fallback_chains`] does.
+        pub fn renderable_item(&self, id: &str, supported: &[MediaType]) -> Option<&Item> {
+            let map = self.items.iter()
                 .map(|i| (&i.id, i))
                 .collect::<HashMap<_, _>>();
-            map.iter()
-                .try_for_each(|(_, item)| {
-                    fn chain(item: &&Item, map: &HashMap<&String, &Item>, vec: &mut Vec<String>) -> Result<(), failure::Error> {
-                        if item.media_type.is_core_media_type() {
-                            Ok(())
-                        } else {
-                            let fallback_id = item.fallback.clone().ok_or(EPUBError::PackageDocumentError {
-                                err_msg: format!("Fallback not found in non core media type <item>: {:?}", &item),
-                            })?;
-                            let fallback = map.get(&fallback_id).ok_or(EPUBError::PackageDocumentError {
-                                err_msg: format!("Fallback {} is not found", fallback_id)
-                            })?;
-                            if vec.contains(&fallback_id) {
-                                return Err(EPUBError::PackageDocumentError {
-                                    err_msg: format!("Fallback chain has circular-references: {:?}", vec)
-                                }.into());
-                            } else {
-                                vec.push(fallback_id);
-                                chain(fallback, map, vec)
-                            }
-                        }
-                    };
-                    let mut history = Vec::new();
-                    chain(item, &map, &mut history)
-                })?;
 
-            Ok(Self { id, items, cover_image, nav })
+            let mut item = *map.get(&id.to_string())?;
+            let mut history = Vec::new();
+
+            loop {
+                if supported.contains(&item.media_type) || item.media_type.is_core_media_type() {
+                    return Some(item);
+                }
+
+                let fallback_id = item.fallback.clone()?;
+                if history.contains(&fallback_id) {
+                    return None;
+                }
+                history.push(fallback_id.clone());
+
+                item = *map.get(&fallback_id)?;
+            }
+        }
+    }
+
+    /// Every non-core-media-type [`Item`] must declare a `fallback` resolving,
+    /// through zero or more further fallbacks, to an item the reading system can
+    /// render — the same check [`Manifest::new`] runs on items parsed from XML.
+    fn check_fallback_chains(items: &HashSet<Item>) -> Result<(), Error> {
+        let map = items.iter()
+            .map(|i| (&i.id, i))
+            .collect::<HashMap<_, _>>();
+
+        map.iter().try_for_each(|(_, item)| {
+            fn chain(item: &&Item, map: &HashMap<&String, &Item>, vec: &mut Vec<String>) -> Result<(), failure::Error> {
+                if item.media_type.is_core_media_type() {
+                    Ok(())
+                } else {
+                    let fallback_id = item.fallback.clone().ok_or(EPUBError::PackageDocumentError {
+                        err_msg: format!("Fallback not found in non core media type <item>: {:?}", &item),
+                    })?;
+                    let fallback = map.get(&fallback_id).ok_or(EPUBError::PackageDocumentError {
+                        err_msg: format!("Fallback {} is not found", fallback_id)
+                    })?;
+                    if vec.contains(&fallback_id) {
+                        return Err(EPUBError::PackageDocumentError {
+                            err_msg: format!("Fallback chain has circular-references: {:?}", vec)
+                        }.into());
+                    } else {
+                        vec.push(fallback_id);
+                        chain(fallback, map, vec)
+                    }
+                }
+            };
+            let mut history = Vec::new();
+            chain(item, &map, &mut history)
+        })
+    }
+
+    /// Builds a [`Manifest`] in memory, validating on [`ManifestBuilder::build`] that
+    /// exactly one item has the `nav` property (where [`Manifest::new`] only requires
+    /// at least one), and, as [`Manifest::new`] does, a resolvable, non-circular
+    /// `fallback` chain for every non-core-media-type item.
+    #[derive(Debug, Clone, Default)]
+    pub struct ManifestBuilder {
+        id: Option<String>,
+        items: Vec<Item>,
+    }
+
+    impl ManifestBuilder {
+        pub fn id(mut self, id: impl Into<String>) -> Self {
+            self.id = Some(id.into());
+            self
+        }
+
+        pub fn item(mut self, item: Item) -> Self {
+            self.items.push(item);
+            self
+        }
+
+        pub fn build(self) -> Result<Manifest, Error> {
+            let items: HashSet<Item> = self.items.into_iter().collect();
+
+            let cover_image = items.iter()
+                .find(|i| i.properties.contains(&ManifestItemProperty::cover_image))
+                .cloned();
+
+            let nav_items: Vec<&Item> = items.iter()
+                .filter(|i| i.properties.contains(&ManifestItemProperty::nav))
+                .collect();
+            let nav = match nav_items.as_slice() {
+                [nav] => (*nav).clone(),
+                [] => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Manifest needs exactly one item with the `nav` property.".to_string()
+                }.into()),
+                _ => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Manifest has more than one item with the `nav` property.".to_string()
+                }.into()),
+            };
+
+            check_fallback_chains(&items)?;
+
+            Ok(Manifest { id: self.id, items, cover_image, nav })
         }
     }
 
     #[derive(Clone, Eq, PartialEq, Hash, Debug)]
     pub struct Item {
         fallback: Option<String>,
-        href: String,
-        id: String,
+        pub(crate) href: String,
+        pub(crate) id: String,
         media_overlay: Option<String>,
         media_type: MediaType,
         properties: Vec<ManifestItemProperty>,
@@ -836,33 +1267,34 @@ pub mod manifest {
         }
     }
 
-    impl TryFrom<&XmlElement> for Item {
-        type Error = ();
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            Item::from_xml_element(value, |_elem, attrs| {
+    impl FromXml for Item {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            Item::from_xml_result(value, |_elem, attrs| {
                 let fallback = Item::get_attr(attrs, "fallback");
-                let id = Item::id(attrs)?;
-                // .ok_or(EPUBError::PackageDocumentError {
-                //     err_msg: "ID is undefined on <item>".to_string()
-                // })?;
-                let href = Item::get_attr(attrs, "href")?;
-                // .ok_or(EPUBError::PackageDocumentError {
-                //     err_msg: "Href is undefined on <item>".to_string()
-                // })?;
+                let id = Item::required_attr(attrs, "id")?;
+                let href = Item::required_attr(attrs, "href")?;
                 let media_overlay = Item::get_attr(attrs, "media-overlay");
-                let media_type = Item::get_attr(attrs, "media-type")
-                    .map(|s| MediaType::from_str(&s).ok())
-                    .flatten()?;
-                // .ok_or(EPUBError::PackageDocumentError {
-                //     err_msg: "Media-type is undefined on <item>".to_string()
-                // })?;
+                let media_type_str = Item::required_attr(attrs, "media-type")?;
+                let media_type = {
+                    use crate::media_type::{ApplicationType as A, AudioType as Au, ImageType as I, TextType as T};
+                    [
+                        MediaType::Image(I::GIF), MediaType::Image(I::JPEG), MediaType::Image(I::PNG), MediaType::Image(I::SVG),
+                        MediaType::Image(I::WEBP), MediaType::Application(A::XHTML), MediaType::Application(A::OpenType),
+                        MediaType::Application(A::WOFF), MediaType::Application(A::MediaOverlays), MediaType::Application(A::PLS),
+                        MediaType::Application(A::NCX), MediaType::Audio(Au::MPEG), MediaType::Audio(Au::MP4),
+                        MediaType::Audio(Au::OGG), MediaType::Text(T::CSS), MediaType::Text(T::JS),
+                    ].iter().find(|t| t.to_string() == media_type_str).cloned()
+                }
+                    .ok_or_else(|| EPUBError::PackageDocumentError {
+                        err_msg: format!("Invalid media-type on <item>: {}", media_type_str)
+                    })?;
                 let properties = Item::get_attr(attrs, "properties")
                     .iter()
                     .flat_map(|s| s.split_whitespace())
                     .flat_map(|s| ManifestItemProperty::from_str(s))
                     .collect::<Vec<ManifestItemProperty>>();
 
-                Some(
+                Ok(
                     Item {
                         fallback,
                         href,
@@ -873,8 +1305,66 @@ pub mod manifest {
                     }
                 )
             })
-                .flatten()
-                .ok_or(())
+        }
+    }
+
+    impl Item {
+        /// Builds a manifest `<item>` from its required attributes; optional ones
+        /// default to empty/`None` and are set with the chained `fallback`/
+        /// `media_overlay`/`property` methods.
+        pub fn new(id: impl Into<String>, href: impl Into<String>, media_type: MediaType) -> Self {
+            Self {
+                fallback: None,
+                href: href.into(),
+                id: id.into(),
+                media_overlay: None,
+                media_type,
+                properties: Vec::new(),
+            }
+        }
+
+        pub fn fallback(mut self, fallback: impl Into<String>) -> Self {
+            self.fallback = Some(fallback.into());
+            self
+        }
+
+        pub fn media_overlay(mut self, media_overlay: impl Into<String>) -> Self {
+            self.media_overlay = Some(media_overlay.into());
+            self
+        }
+
+        pub fn property(mut self, property: ManifestItemProperty) -> Self {
+            self.properties.push(property);
+            self
+        }
+
+        pub fn media_type(&self) -> &MediaType {
+            &self.media_type
+        }
+
+        pub fn media_overlay_ref(&self) -> Option<&String> {
+            self.media_overlay.as_ref()
+        }
+
+        /// Serializes this item as an OPF `<item>` element.
+        fn to_opf_xml(&self) -> String {
+            let mut xml = format!(
+                r#"<item id="{id}" href="{href}" media-type="{media_type}""#,
+                id = escape_xml(&self.id), href = escape_xml(&self.href), media_type = self.media_type.to_string(),
+            );
+
+            if let Some(fallback) = &self.fallback {
+                xml += &format!(r#" fallback="{}""#, escape_xml(fallback));
+            }
+            if let Some(media_overlay) = &self.media_overlay {
+                xml += &format!(r#" media-overlay="{}""#, escape_xml(media_overlay));
+            }
+            if !self.properties.is_empty() {
+                let properties = self.properties.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ");
+                xml += &format!(r#" properties="{}""#, escape_xml(&properties));
+            }
+
+            xml + "/>"
         }
     }
 
@@ -1019,6 +1509,78 @@ pub mod manifest {
 
             Ok(())
         }
+
+        /// A [`Manifest`] built in memory must round-trip through
+        /// [`Manifest::to_opf_xml`] and back through [`Manifest::new`] unchanged.
+        #[test]
+        fn round_trips_through_opf_xml() -> Result<(), Error> {
+            use crate::media_type::{ApplicationType, TextType};
+
+            let manifest = Manifest::builder()
+                .id("manifest-id")
+                .item(Item::new("nav", "nav.xhtml", MediaType::Application(ApplicationType::XHTML))
+                    .property(ManifestItemProperty::nav))
+                .item(Item::new("css01", "css/epub.css", MediaType::Text(TextType::CSS)))
+                .build()?;
+
+            let elem = parse_opf_element(&manifest.to_opf_xml());
+            let round_tripped = Manifest::new(&elem)?;
+
+            assert_eq!(manifest, round_tripped);
+
+            Ok(())
+        }
+
+        /// [`Manifest::renderable_item`] should follow a non-core-media-type item's
+        /// `fallback` chain until it reaches one of the `supported` types, and
+        /// [`ManifestBuilder::build`] should reject a non-core item with no
+        /// `fallback` at all.
+        #[test]
+        fn renderable_item_follows_fallback_chain() -> Result<(), Error> {
+            use crate::media_type::{ApplicationType, AudioType, ImageType};
+
+            let manifest = Manifest::builder()
+                .id("manifest-id")
+                .item(Item::new("nav", "nav.xhtml", MediaType::Application(ApplicationType::XHTML))
+                    .property(ManifestItemProperty::nav))
+                .item(Item::new("cover-ogg", "cover.ogg", MediaType::Audio(AudioType::OGG))
+                    .fallback("cover-mp3"))
+                .item(Item::new("cover-mp3", "cover.mp3", MediaType::Audio(AudioType::MPEG)))
+                .build()?;
+
+            assert_eq!(
+                manifest.renderable_item("cover-ogg", &[]).map(|i| i.id.as_str()),
+                Some("cover-mp3"),
+            );
+            assert_eq!(
+                manifest.renderable_item("cover-ogg", &[MediaType::Audio(AudioType::OGG)]).map(|i| i.id.as_str()),
+                Some("cover-ogg"),
+            );
+
+            let missing_fallback = Manifest::builder()
+                .id("manifest-id")
+                .item(Item::new("nav", "nav.xhtml", MediaType::Application(ApplicationType::XHTML))
+                    .property(ManifestItemProperty::nav))
+                .item(Item::new("cover-webp", "cover.webp", MediaType::Image(ImageType::WEBP)))
+                .build();
+
+            assert!(missing_fallback.is_err());
+
+            Ok(())
+        }
+
+        /// Parses a single top-level OPF element (e.g. the `<manifest>` produced by
+        /// [`Manifest::to_opf_xml`]) back into an [`XmlElement`], for round-trip tests.
+        fn parse_opf_element(xml: &str) -> XmlElement {
+            let parser = xml::EventReader::new(std::io::Cursor::new(xml.as_bytes().to_vec()));
+            let tree = Xml::new(&mut parser.into_iter().peekable());
+            tree.vec.into_iter().next()
+                .and_then(|doc| doc.children.into_iter().find(|e| match e.event {
+                    XmlEvent::StartElement { .. } => true,
+                    _ => false,
+                }))
+                .expect("parsed element")
+        }
     }
 }
 
@@ -1030,26 +1592,21 @@ pub mod spine {
     pub struct Spine {
         id: Option<String>,
         page_progression_direction: Option<PageProgressionDirection>,
-        items: Vec<ItemRef>,
+        /// The manifest `id` of the EPUB 2 NCX document, for readers without an
+        /// EPUB 3 navigation document.
+        pub toc: Option<String>,
+        pub(crate) items: Vec<ItemRef>,
     }
 
     impl Spine {
-        pub fn new(package_element: &XmlElement) -> Result<Self, Error> {
-            let (spine_elem, attributes) = package_element.children.iter()
-                .find_map(|e| match &e.event {
-                    XmlEvent::StartElement {
-                        name,
-                        attributes, ..
-                    } => if &name.local_name == "spine" {
-                        Some((e, attributes))
-                    } else {
-                        None
-                    }
-                    _ => None
-                })
-                .ok_or(EPUBError::PackageDocumentError {
-                    err_msg: "Spine element not found.".to_string()
-                })?;
+        /// Builds a `Spine` directly from an already-located `<spine>` element.
+        pub fn new(spine_elem: &XmlElement) -> Result<Self, Error> {
+            let attributes = match &spine_elem.event {
+                XmlEvent::StartElement { attributes, .. } => attributes,
+                _ => return Err(EPUBError::PackageDocumentError {
+                    err_msg: "Expected <spine> element.".to_string()
+                }.into()),
+            };
 
             let id = attributes.iter()
                 .find_map(|a| if &a.name.local_name == "id" {
@@ -1062,24 +1619,161 @@ pub mod spine {
                         PageProgressionDirection::from_str(&a.value).ok()
                     } else { None });
 
-            let items = spine_elem.children.iter()
-                .flat_map(|e| ItemRef::try_from(e).ok())
-                .collect();
+            let toc = attributes.iter()
+                .find_map(|a| if &a.name.local_name == "toc" {
+                    Some(a.value.to_string())
+                } else { None });
+
+            let items = spine_elem.parse_children::<ItemRef>();
 
             Ok(Self {
                 id,
                 page_progression_direction,
+                toc,
                 items,
             })
         }
+
+        /// Resolves the spine's `<itemref idref="...">`s against `manifest`'s items,
+        /// giving the book's reading order as an ordered list of manifest [`Item`]s.
+        /// Errors if an `idref` doesn't match any manifest item.
+        pub fn resolve<'a>(&self, manifest: &'a Manifest) -> Result<Vec<&'a Item>, Error> {
+            self.items.iter()
+                .map(|item_ref| {
+                    manifest.items.iter()
+                        .find(|item| item.id == item_ref.idref)
+                        .ok_or(EPUBError::PackageDocumentError {
+                            err_msg: format!("Spine itemref idref {:?} matches no manifest item.", item_ref.idref),
+                        }.into())
+                })
+                .collect()
+        }
+
+        /// Like [`Spine::resolve`], but drops items whose `<itemref>` has
+        /// `linear="no"`, leaving just the primary linear narrative.
+        pub fn resolve_linear<'a>(&self, manifest: &'a Manifest) -> Result<Vec<&'a Item>, Error> {
+            self.items.iter()
+                .filter(|item_ref| item_ref.linear.unwrap_or_default() != Linear::no)
+                .map(|item_ref| {
+                    manifest.items.iter()
+                        .find(|item| item.id == item_ref.idref)
+                        .ok_or(EPUBError::PackageDocumentError {
+                            err_msg: format!("Spine itemref idref {:?} matches no manifest item.", item_ref.idref),
+                        }.into())
+                })
+                .collect()
+        }
+
+        /// Starts assembling a `Spine` in memory, e.g. for writing a new EPUB.
+        pub fn builder() -> SpineBuilder {
+            SpineBuilder::default()
+        }
+
+        /// Serializes this spine back to a full `<spine>` OPF element.
+        pub fn to_opf_xml(&self) -> String {
+            let id_attr = self.id.as_ref()
+                .map(|id| format!(r#" id="{}""#, escape_xml(id)))
+                .unwrap_or_default();
+            let ppd_attr = self.page_progression_direction.as_ref()
+                .map(|ppd| format!(r#" page-progression-direction="{}""#, ppd.to_string()))
+                .unwrap_or_default();
+            let toc_attr = self.toc.as_ref()
+                .map(|toc| format!(r#" toc="{}""#, escape_xml(toc)))
+                .unwrap_or_default();
+            let items: String = self.items.iter().map(ItemRef::to_opf_xml).collect();
+            format!("<spine xmlns=\"http://www.idpf.org/2007/opf\"{}{}{}>{}</spine>", id_attr, ppd_attr, toc_attr, items)
+        }
+
+        /// The rendition layout (reflowable vs. pre-paginated) declared on any
+        /// `<itemref>`'s `properties`, if one is present.
+        pub fn layout(&self) -> Option<SpineItemProperty> {
+            self.spine_item_property(|p| matches!(
+                p,
+                SpineItemProperty::rendition_layout_reflowable | SpineItemProperty::rendition_layout_pre_paginated
+            ))
+        }
+
+        /// The rendition orientation declared on any `<itemref>`'s `properties`,
+        /// if one is present.
+        pub fn orientation(&self) -> Option<SpineItemProperty> {
+            self.spine_item_property(|p| matches!(
+                p,
+                SpineItemProperty::rendition_orientation_landscape
+                    | SpineItemProperty::rendition_orientation_portrait
+                    | SpineItemProperty::rendition_orientation_auto
+            ))
+        }
+
+        /// The rendition spread behavior declared on any `<itemref>`'s
+        /// `properties`, if one is present.
+        pub fn spread(&self) -> Option<SpineItemProperty> {
+            self.spine_item_property(|p| matches!(
+                p,
+                SpineItemProperty::rendition_spread_none
+                    | SpineItemProperty::rendition_spread_landscape
+                    | SpineItemProperty::rendition_spread_both
+                    | SpineItemProperty::rendition_spread_auto
+            ))
+        }
+
+        fn spine_item_property(&self, f: impl Fn(&SpineItemProperty) -> bool) -> Option<SpineItemProperty> {
+            self.items.iter()
+                .flat_map(|item_ref| item_ref.properties.iter())
+                .find(|p| f(p))
+                .copied()
+        }
     }
 
-    #[derive(Debug, Eq, PartialEq)]
+    /// Builds a [`Spine`] in memory, validating on [`SpineBuilder::build`] that
+    /// every `<itemref idref>` resolves against the given [`Manifest`], the same
+    /// invariant [`Spine::resolve`] checks for a spine parsed from XML.
+    #[derive(Debug, Clone, Default)]
+    pub struct SpineBuilder {
+        id: Option<String>,
+        page_progression_direction: Option<PageProgressionDirection>,
+        toc: Option<String>,
+        items: Vec<ItemRef>,
+    }
+
+    impl SpineBuilder {
+        pub fn id(mut self, id: impl Into<String>) -> Self {
+            self.id = Some(id.into());
+            self
+        }
+
+        pub fn page_progression_direction(mut self, direction: PageProgressionDirection) -> Self {
+            self.page_progression_direction = Some(direction);
+            self
+        }
+
+        pub fn toc(mut self, toc: impl Into<String>) -> Self {
+            self.toc = Some(toc.into());
+            self
+        }
+
+        pub fn item(mut self, item_ref: ItemRef) -> Self {
+            self.items.push(item_ref);
+            self
+        }
+
+        pub fn build(self, manifest: &Manifest) -> Result<Spine, Error> {
+            let spine = Spine {
+                id: self.id,
+                page_progression_direction: self.page_progression_direction,
+                toc: self.toc,
+                items: self.items,
+            };
+            spine.resolve(manifest)?;
+            Ok(spine)
+        }
+    }
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
     pub struct ItemRef {
         id: Option<String>,
-        idref: String,
+        pub(crate) idref: String,
         linear: Option<Linear>,
-        properties: Option<String>,
+        properties: Vec<SpineItemProperty>,
     }
 
     impl Element for ItemRef {
@@ -1092,22 +1786,73 @@ pub mod spine {
         }
     }
 
-    impl TryFrom<&XmlElement> for ItemRef {
-        type Error = ();
-
-        fn try_from(value: &XmlElement) -> Result<Self, Self::Error> {
-            ItemRef::from_xml_element(value, |_elem, attrs| {
+    impl FromXml for ItemRef {
+        fn from_xml(value: &XmlElement) -> Result<Self, EPUBError> {
+            ItemRef::from_xml_result(value, |_elem, attrs| {
                 let id = ItemRef::id(attrs);
-                let idref = ItemRef::get_attr(attrs, "idref")?;
+                let idref = ItemRef::required_attr(attrs, "idref")?;
                 let linear = ItemRef::get_attr(attrs, "linear")
                     .map(|s| Linear::from_str(&s).ok())
                     .flatten();
-                let properties = ItemRef::get_attr(attrs, "properties");
+                let properties = ItemRef::get_attr(attrs, "properties")
+                    .iter()
+                    .flat_map(|s| s.split_whitespace())
+                    .flat_map(|s| SpineItemProperty::from_str(s))
+                    .collect::<Vec<SpineItemProperty>>();
 
-                Some(ItemRef { id, idref, linear, properties })
+                Ok(ItemRef { id, idref, linear, properties })
             })
-                .flatten()
-                .ok_or(())
+        }
+    }
+
+    impl ItemRef {
+        /// Builds a spine `<itemref>` referencing the manifest item `idref`;
+        /// optional attributes are set with the chained `id`/`linear`/`property`
+        /// methods.
+        pub fn new(idref: impl Into<String>) -> Self {
+            Self {
+                id: None,
+                idref: idref.into(),
+                linear: None,
+                properties: Vec::new(),
+            }
+        }
+
+        pub fn with_id(mut self, id: impl Into<String>) -> Self {
+            self.id = Some(id.into());
+            self
+        }
+
+        pub fn linear(mut self, linear: Linear) -> Self {
+            self.linear = Some(linear);
+            self
+        }
+
+        pub fn property(mut self, property: SpineItemProperty) -> Self {
+            self.properties.push(property);
+            self
+        }
+
+        pub fn properties(&self) -> &[SpineItemProperty] {
+            &self.properties
+        }
+
+        /// Serializes this itemref as an OPF `<itemref>` element.
+        fn to_opf_xml(&self) -> String {
+            let mut xml = format!(r#"<itemref idref="{}""#, escape_xml(&self.idref));
+
+            if let Some(id) = &self.id {
+                xml += &format!(r#" id="{}""#, escape_xml(id));
+            }
+            if let Some(linear) = &self.linear {
+                xml += &format!(r#" linear="{}""#, linear.to_string());
+            }
+            if !self.properties.is_empty() {
+                let properties = self.properties.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ");
+                xml += &format!(r#" properties="{}""#, escape_xml(&properties));
+            }
+
+            xml + "/>"
         }
     }
 
@@ -1169,7 +1914,63 @@ pub mod spine {
         }
     }
 
-    // todo impl properties
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Copy, Clone, Debug, Hash)]
+    pub enum SpineItemProperty {
+        page_spread_left,
+        page_spread_right,
+        rendition_page_spread_center,
+        rendition_layout_reflowable,
+        rendition_layout_pre_paginated,
+        rendition_orientation_landscape,
+        rendition_orientation_portrait,
+        rendition_orientation_auto,
+        rendition_spread_none,
+        rendition_spread_landscape,
+        rendition_spread_both,
+        rendition_spread_auto,
+    }
+
+    impl FromStr for SpineItemProperty {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "page-spread-left" => Ok(SpineItemProperty::page_spread_left),
+                "page-spread-right" => Ok(SpineItemProperty::page_spread_right),
+                "rendition:page-spread-center" => Ok(SpineItemProperty::rendition_page_spread_center),
+                "rendition:layout-reflowable" => Ok(SpineItemProperty::rendition_layout_reflowable),
+                "rendition:layout-pre-paginated" => Ok(SpineItemProperty::rendition_layout_pre_paginated),
+                "rendition:orientation-landscape" => Ok(SpineItemProperty::rendition_orientation_landscape),
+                "rendition:orientation-portrait" => Ok(SpineItemProperty::rendition_orientation_portrait),
+                "rendition:orientation-auto" => Ok(SpineItemProperty::rendition_orientation_auto),
+                "rendition:spread-none" => Ok(SpineItemProperty::rendition_spread_none),
+                "rendition:spread-landscape" => Ok(SpineItemProperty::rendition_spread_landscape),
+                "rendition:spread-both" => Ok(SpineItemProperty::rendition_spread_both),
+                "rendition:spread-auto" => Ok(SpineItemProperty::rendition_spread_auto),
+                _ => Err(())
+            }
+        }
+    }
+
+    impl ToString for SpineItemProperty {
+        fn to_string(&self) -> String {
+            match self {
+                SpineItemProperty::page_spread_left => "page-spread-left",
+                SpineItemProperty::page_spread_right => "page-spread-right",
+                SpineItemProperty::rendition_page_spread_center => "rendition:page-spread-center",
+                SpineItemProperty::rendition_layout_reflowable => "rendition:layout-reflowable",
+                SpineItemProperty::rendition_layout_pre_paginated => "rendition:layout-pre-paginated",
+                SpineItemProperty::rendition_orientation_landscape => "rendition:orientation-landscape",
+                SpineItemProperty::rendition_orientation_portrait => "rendition:orientation-portrait",
+                SpineItemProperty::rendition_orientation_auto => "rendition:orientation-auto",
+                SpineItemProperty::rendition_spread_none => "rendition:spread-none",
+                SpineItemProperty::rendition_spread_landscape => "rendition:spread-landscape",
+                SpineItemProperty::rendition_spread_both => "rendition:spread-both",
+                SpineItemProperty::rendition_spread_auto => "rendition:spread-auto",
+            }.to_string()
+        }
+    }
 
     #[cfg(test)]
     mod test {
@@ -1188,24 +1989,25 @@ pub mod spine {
                 let correct = Spine {
                     id: None,
                     page_progression_direction: None,
+                    toc: None,
                     items: vec![
                         ItemRef {
                             id: None,
                             idref: "cover".into(),
                             linear: None,
-                            properties: None,
+                            properties: vec![],
                         },
                         ItemRef {
                             id: None,
                             idref: "nav".into(),
                             linear: None,
-                            properties: None,
+                            properties: vec![],
                         },
                         ItemRef {
                             id: None,
                             idref: "s04".into(),
                             linear: None,
-                            properties: None,
+                            properties: vec![],
                         }
                     ],
                 };
@@ -1216,6 +2018,44 @@ pub mod spine {
 
             Ok(())
         }
+
+        /// A [`Spine`] built in memory must round-trip through
+        /// [`Spine::to_opf_xml`] and back through [`Spine::new`] unchanged.
+        #[test]
+        fn round_trips_through_opf_xml() -> Result<(), Error> {
+            use super::super::manifest::ManifestItemProperty;
+            use crate::media_type::{ApplicationType, MediaType};
+
+            let manifest = Manifest::builder()
+                .item(Item::new("nav", "nav.xhtml", MediaType::Application(ApplicationType::XHTML))
+                    .property(ManifestItemProperty::nav))
+                .build()?;
+
+            let spine = Spine::builder()
+                .toc("ncx")
+                .item(ItemRef::new("nav"))
+                .build(&manifest)?;
+
+            let elem = parse_opf_element(&spine.to_opf_xml());
+            let round_tripped = Spine::new(&elem)?;
+
+            assert_eq!(spine, round_tripped);
+
+            Ok(())
+        }
+
+        /// Parses a single top-level OPF element (e.g. the `<spine>` produced by
+        /// [`Spine::to_opf_xml`]) back into an [`XmlElement`], for round-trip tests.
+        fn parse_opf_element(xml: &str) -> XmlElement {
+            let parser = xml::EventReader::new(std::io::Cursor::new(xml.as_bytes().to_vec()));
+            let tree = Xml::new(&mut parser.into_iter().peekable());
+            tree.vec.into_iter().next()
+                .and_then(|doc| doc.children.into_iter().find(|e| match e.event {
+                    XmlEvent::StartElement { .. } => true,
+                    _ => false,
+                }))
+                .expect("parsed element")
+        }
     }
 }
 
@@ -0,0 +1,214 @@
+use crate::util::{Xml, XmlElement};
+use xml::reader::XmlEvent;
+
+/// An inline style a content document can mark up a text run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Italic,
+    Heading,
+    Link,
+}
+
+/// A point where a [`Style`] begins or ends, at a byte offset into [`Chapter::text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleTransition {
+    On(Style),
+    Off(Style),
+}
+
+/// Elements whose entire subtree is dropped when extracting a chapter.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "svg", "nav", "iframe", "head"];
+
+/// Elements that insert a line break and close out a [`Chapter::lines`] entry.
+const BLOCK_ELEMENTS: &[&str] = &["p", "div", "br", "li", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// A spine item's content document, reduced to plain text plus enough structure
+/// (paragraph/line byte ranges and inline style runs) to render it in a terminal
+/// or feed a search index without re-walking the original XHTML.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub text: String,
+    pub lines: Vec<(usize, usize)>,
+    pub attrs: Vec<(usize, StyleTransition)>,
+}
+
+/// Walks a parsed XHTML content document and reduces it to a [`Chapter`]: text
+/// nodes are entity-decoded already (by the XML parser) and whitespace-collapsed
+/// here, `script`/`style`/`svg`/`nav`/`iframe` subtrees are skipped entirely,
+/// block-level elements start a new line, and `b`/`strong`/`i`/`em`/`h1..h6`/`a`
+/// elements push a style transition at the offset where they open and close.
+pub fn extract_chapter(xml: &Xml) -> Chapter {
+    let title = find_title(xml).unwrap_or_default();
+
+    let mut walker = Walker {
+        text: String::new(),
+        lines: Vec::new(),
+        attrs: Vec::new(),
+        line_start: 0,
+        last_was_space: false,
+    };
+
+    for elem in xml.iter() {
+        walker.walk(elem);
+    }
+    walker.close_line();
+
+    Chapter {
+        title,
+        text: walker.text,
+        lines: walker.lines,
+        attrs: walker.attrs,
+    }
+}
+
+struct Walker {
+    text: String,
+    lines: Vec<(usize, usize)>,
+    attrs: Vec<(usize, StyleTransition)>,
+    line_start: usize,
+    last_was_space: bool,
+}
+
+impl Walker {
+    fn walk(&mut self, elem: &XmlElement) {
+        match &elem.event {
+            XmlEvent::StartElement { name, .. } if SKIPPED_ELEMENTS.contains(&name.local_name.as_str()) => {}
+            XmlEvent::Characters(s) | XmlEvent::CData(s) => self.push_text(s),
+            XmlEvent::StartElement { name, .. } => {
+                let local_name = name.local_name.as_str();
+                let style = style_for(local_name);
+
+                if let Some(style) = style {
+                    self.attrs.push((self.text.len(), StyleTransition::On(style)));
+                }
+
+                for child in &elem.children {
+                    self.walk(child);
+                }
+
+                if let Some(style) = style {
+                    self.attrs.push((self.text.len(), StyleTransition::Off(style)));
+                }
+
+                if BLOCK_ELEMENTS.contains(&local_name) {
+                    self.close_line();
+                }
+            }
+            XmlEvent::StartDocument { .. } => {
+                for child in &elem.children {
+                    self.walk(child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, s: &str) {
+        for c in s.chars() {
+            if c.is_whitespace() {
+                if !self.last_was_space {
+                    self.text.push(' ');
+                }
+                self.last_was_space = true;
+            } else {
+                self.text.push(c);
+                self.last_was_space = false;
+            }
+        }
+    }
+
+    fn close_line(&mut self) {
+        let end = self.text.len();
+        if end > self.line_start {
+            self.lines.push((self.line_start, end));
+        }
+        self.text.push('\n');
+        self.line_start = self.text.len();
+    }
+}
+
+fn style_for(local_name: &str) -> Option<Style> {
+    match local_name {
+        "b" | "strong" => Some(Style::Bold),
+        "i" | "em" => Some(Style::Italic),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some(Style::Heading),
+        "a" => Some(Style::Link),
+        _ => None,
+    }
+}
+
+fn find_title(xml: &Xml) -> Option<String> {
+    xml.iter().find_map(find_title_elem)
+        .map(|e| e.inner_text().trim().to_string())
+}
+
+fn find_title_elem(elem: &XmlElement) -> Option<&XmlElement> {
+    match &elem.event {
+        XmlEvent::StartElement { name, .. } if name.local_name == "title" => return Some(elem),
+        _ => {}
+    }
+    elem.children.iter().find_map(find_title_elem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xhtml: &str) -> Xml {
+        let parser = xml::EventReader::new(std::io::Cursor::new(xhtml.as_bytes().to_vec()));
+        Xml::new(&mut parser.into_iter().peekable())
+    }
+
+    #[test]
+    fn extracts_title_and_plain_text() {
+        let chapter = extract_chapter(&parse(
+            "<html><head><title>Chapter 1</title></head><body><p>Hello, world!</p></body></html>"
+        ));
+
+        assert_eq!(chapter.title, "Chapter 1");
+        assert_eq!(chapter.text.trim(), "Hello, world!");
+    }
+
+    #[test]
+    fn collapses_whitespace_across_separate_text_nodes() {
+        let chapter = extract_chapter(&parse(
+            "<html><body><p>Hello   <b>wonderful</b>   world</p></body></html>"
+        ));
+
+        assert_eq!(chapter.text.trim(), "Hello wonderful world");
+    }
+
+    #[test]
+    fn skips_script_and_style_subtrees() {
+        let chapter = extract_chapter(&parse(
+            "<html><body><script>var x = 1;</script><style>p { color: red; }</style><p>Visible</p></body></html>"
+        ));
+
+        assert_eq!(chapter.text.trim(), "Visible");
+    }
+
+    #[test]
+    fn block_elements_start_a_new_line() {
+        let chapter = extract_chapter(&parse(
+            "<html><body><p>First</p><p>Second</p></body></html>"
+        ));
+
+        assert_eq!(chapter.lines.len(), 2);
+        let first = &chapter.text[chapter.lines[0].0..chapter.lines[0].1];
+        let second = &chapter.text[chapter.lines[1].0..chapter.lines[1].1];
+        assert_eq!(first, "First");
+        assert_eq!(second, "Second");
+    }
+
+    #[test]
+    fn records_a_style_transition_for_bold_text() {
+        let chapter = extract_chapter(&parse(
+            "<html><body><p><b>Bold</b></p></body></html>"
+        ));
+
+        assert!(chapter.attrs.contains(&(0, StyleTransition::On(Style::Bold))));
+        assert!(chapter.attrs.contains(&(4, StyleTransition::Off(Style::Bold))));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::media_type::MediaType;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Rewrites every `<img src>`, `<link href>`, and `<script src>` reference, plus
+/// any CSS `url(...)`, in an XHTML content document into a base64 `data:` URI,
+/// by resolving each href/src through `resolve` — which should read the
+/// referenced entry out of the EPUB archive and pair it with a sniffed
+/// [`MediaType`], or return `None` if it can't be resolved, in which case the
+/// original reference is left untouched rather than aborting the document.
+pub fn inline_document(
+    content: &str,
+    mut resolve: impl FnMut(&str) -> Option<(Vec<u8>, MediaType)>,
+) -> String {
+    let content = rewrite_tag_attr(content, "img", "src", &mut resolve);
+    let content = rewrite_tag_attr(&content, "link", "href", &mut resolve);
+    let content = rewrite_tag_attr(&content, "script", "src", &mut resolve);
+    rewrite_css_urls(&content, &mut resolve)
+}
+
+/// Decodes XML's five built-in entity references (`&amp;`, `&lt;`, `&gt;`,
+/// `&quot;`, `&apos;`) in a raw attribute value, so a href escaped by an XML
+/// serializer (e.g. `a&amp;b.png`) still matches the literal archive path
+/// `resolve` expects.
+fn decode_xml_entities(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(idx) = rest.find('&') {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx..];
+        let (decoded, consumed) = ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"].iter()
+            .zip(["&", "<", ">", "\"", "'"].iter())
+            .find_map(|(entity, ch)| after.starts_with(*entity).then(|| (*ch, entity.len())))
+            .unwrap_or(("&", 1));
+        out.push_str(decoded);
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn data_uri(media_type: &MediaType, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", media_type.to_string(), base64_encode(bytes))
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+        let n = ((b0 as u32) << 16) | ((b1 as u32) << 8) | (b2 as u32);
+
+        out.push(BASE64_ALPHABET[((n >> 18) & 0x3F) as usize] as char);
+        out.push(BASE64_ALPHABET[((n >> 12) & 0x3F) as usize] as char);
+        out.push(if chunk.len() > 1 { BASE64_ALPHABET[((n >> 6) & 0x3F) as usize] as char } else { '=' });
+        out.push(if chunk.len() > 2 { BASE64_ALPHABET[(n & 0x3F) as usize] as char } else { '=' });
+    }
+    out
+}
+
+/// Finds each `<tag ...>` occurrence and rewrites `attr="..."`/`attr='...'`
+/// within it via `resolve`, leaving everything else untouched.
+fn rewrite_tag_attr(
+    html: &str,
+    tag: &str,
+    attr: &str,
+    resolve: &mut impl FnMut(&str) -> Option<(Vec<u8>, MediaType)>,
+) -> String {
+    let open = format!("<{}", tag);
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(idx) = rest.find(&open) {
+        let after = idx + open.len();
+        out.push_str(&rest[..after]);
+
+        let boundary_ok = rest[after..].chars().next()
+            .map(|c| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(false);
+        if !boundary_ok {
+            rest = &rest[after..];
+            continue;
+        }
+
+        let tag_rest = &rest[after..];
+        match find_tag_close(tag_rest) {
+            Some(end) => {
+                out.push_str(&rewrite_attr_in_tag(&tag_rest[..end], attr, resolve));
+                rest = &tag_rest[end..];
+            }
+            None => {
+                out.push_str(tag_rest);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Finds the byte offset of the `>` that closes a tag's opening `<tag ...>`,
+/// skipping over any `>` inside a `"..."`/`'...'` attribute value (e.g.
+/// `<img alt="a > b" src="x.png">`) rather than stopping at the first one.
+fn find_tag_close(tag_rest: &str) -> Option<usize> {
+    let bytes = tag_rest.as_bytes();
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            None if b == b'>' => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+fn rewrite_attr_in_tag(
+    body: &str,
+    attr: &str,
+    resolve: &mut impl FnMut(&str) -> Option<(Vec<u8>, MediaType)>,
+) -> String {
+    let needle = format!("{}=", attr);
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+
+    loop {
+        match rest.find(&needle) {
+            None => {
+                out.push_str(rest);
+                break;
+            }
+            Some(idx) => {
+                let boundary_ok = idx == 0 || rest.as_bytes()[idx - 1].is_ascii_whitespace();
+                out.push_str(&rest[..idx]);
+                out.push_str(&needle);
+
+                let after = &rest[idx + needle.len()..];
+                if !boundary_ok {
+                    rest = after;
+                    continue;
+                }
+
+                match after.chars().next() {
+                    Some(q) if q == '"' || q == '\'' => {
+                        match after[1..].find(q) {
+                            Some(end) => {
+                                let value = &after[1..1 + end];
+                                let replacement = resolve(&decode_xml_entities(value))
+                                    .map(|(bytes, media_type)| data_uri(&media_type, &bytes))
+                                    .unwrap_or_else(|| value.to_string());
+                                out.push(q);
+                                out.push_str(&replacement);
+                                out.push(q);
+                                rest = &after[1 + end + 1..];
+                            }
+                            None => {
+                                out.push_str(after);
+                                rest = "";
+                            }
+                        }
+                    }
+                    _ => {
+                        rest = after;
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn rewrite_css_urls(
+    html: &str,
+    resolve: &mut impl FnMut(&str) -> Option<(Vec<u8>, MediaType)>,
+) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(idx) = rest.find("url(") {
+        out.push_str(&rest[..idx + 4]);
+        let after = &rest[idx + 4..];
+
+        match after.find(')') {
+            Some(end) => {
+                let raw = after[..end].trim();
+                let unquoted = raw.trim_matches(|c| c == '"' || c == '\'');
+                let replacement = resolve(&decode_xml_entities(unquoted))
+                    .map(|(bytes, media_type)| format!("\"{}\"", data_uri(&media_type, &bytes)))
+                    .unwrap_or_else(|| raw.to_string());
+                out.push_str(&replacement);
+                out.push(')');
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(after);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_type::{ImageType, MediaType};
+
+    fn resolve_all(href: &str) -> Option<(Vec<u8>, MediaType)> {
+        Some((href.as_bytes().to_vec(), MediaType::Image(ImageType::PNG)))
+    }
+
+    #[test]
+    fn inlines_img_src() {
+        let out = inline_document(r#"<img src="a.png">"#, resolve_all);
+        assert_eq!(out, format!(r#"<img src="{}">"#, data_uri(&MediaType::Image(ImageType::PNG), b"a.png")));
+    }
+
+    #[test]
+    fn attribute_value_containing_angle_bracket_does_not_truncate_the_tag() {
+        let out = inline_document(r#"<img alt="a > b" src="a.png">"#, resolve_all);
+        assert_eq!(
+            out,
+            format!(r#"<img alt="a > b" src="{}">"#, data_uri(&MediaType::Image(ImageType::PNG), b"a.png"))
+        );
+    }
+
+    #[test]
+    fn attribute_value_containing_the_other_quote_style() {
+        let out = inline_document(r#"<img alt='a "quoted" word' src="a.png">"#, resolve_all);
+        assert_eq!(
+            out,
+            format!(r#"<img alt='a "quoted" word' src="{}">"#, data_uri(&MediaType::Image(ImageType::PNG), b"a.png"))
+        );
+    }
+
+    #[test]
+    fn decodes_entities_in_href_before_resolving() {
+        let out = inline_document(r#"<img src="a&amp;b.png">"#, resolve_all);
+        assert_eq!(out, format!(r#"<img src="{}">"#, data_uri(&MediaType::Image(ImageType::PNG), b"a&b.png")));
+    }
+
+    #[test]
+    fn unresolved_reference_is_left_untouched() {
+        let out = inline_document(r#"<img src="missing.png">"#, |_| None);
+        assert_eq!(out, r#"<img src="missing.png">"#);
+    }
+
+    #[test]
+    fn inlines_css_url() {
+        let out = inline_document(r#"<style>body { background: url("a.png"); }</style>"#, resolve_all);
+        assert_eq!(
+            out,
+            format!(
+                r#"<style>body {{ background: url("{}"); }}</style>"#,
+                data_uri(&MediaType::Image(ImageType::PNG), b"a.png")
+            )
+        );
+    }
+}
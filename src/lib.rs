@@ -1,16 +1,28 @@
 pub mod read;
 pub mod epub;
+pub mod extract;
+pub mod inline;
 pub mod media_type;
+pub mod render;
+pub mod smil;
+pub mod toc;
+pub mod write;
 
 pub mod prelude {
     pub use crate::EPUBError;
     pub use crate::read::EPUBReader;
     pub use crate::epub::*;
+    pub use crate::extract::{Chapter, Style, StyleTransition};
     pub use crate::media_type::*;
+    pub use crate::smil::{Clip, MediaOverlay};
+    pub use crate::toc::*;
+    pub use crate::write::EPUBWriter;
 }
 
 use failure::Fail;
 
+use crate::media_type::MediaType;
+
 #[derive(Fail, Debug)]
 pub enum EPUBError {
     #[fail(display = "PACKAGE DOCUMENT :ERROR\t: {}", err_msg)]
@@ -21,6 +33,12 @@ pub enum EPUBError {
     MediaTypeError {
         err_msg: String,
     },
+    #[fail(display = "MEDIA TYPE MISMATCH for '{}'\t: manifest declares {:?}, content sniffed as {:?}", resource, declared, detected)]
+    MediaTypeMismatch {
+        resource: String,
+        declared: MediaType,
+        detected: MediaType,
+    },
     #[fail(display = "CONTAINER :ERROR\t: {}", err_msg)]
     ContainerError {
         err_msg: String,
@@ -33,18 +51,27 @@ pub enum EPUBError {
     ZipError {
         error: zip::result::ZipError
     },
-    #[fail(display = "XML ERROR\t: {:?}", error)]
+    #[fail(display = "XML ERROR in '{}'\t: {:?}", resource, error)]
     XMLError {
-        error: xml::reader::Error
+        resource: String,
+        error: xml::reader::Error,
     },
 }
 
 pub mod util {
-    use std::io::Read;
     use std::iter::Peekable;
     use xml::reader::*;
     use failure::_core::ops::Deref;
 
+    use crate::EPUBError;
+
+    /// Maps a parsed [`XmlElement`] directly onto a typed Rust struct, centralizing
+    /// attribute lookups and required/optional field handling so adding support for
+    /// a new package-document element doesn't require hand-written tree walking.
+    pub trait FromXml: Sized {
+        fn from_xml(elem: &XmlElement) -> Result<Self, EPUBError>;
+    }
+
     #[derive(Debug, Clone)]
     pub struct Xml {
         pub vec: Vec<XmlElement>
@@ -59,10 +86,19 @@ pub mod util {
     }
 
     impl Xml {
-        pub fn new<R: Read>(iter: &mut Peekable<Events<R>>) -> Self {
+        pub fn new<I: Iterator<Item = Result<XmlEvent, xml::reader::Error>>>(iter: &mut Peekable<I>) -> Self {
             let mut vec = Vec::new();
 
-            while let Some(_) = iter.peek() {
+            while let Some(item) = iter.peek() {
+                // An unrecoverable parse error leaves `iter` permanently peeked at
+                // the same `Err` (xml-rs keeps yielding it without advancing), and
+                // `XmlElement::new` returns `None` without consuming it. Stop here
+                // instead of spinning forever; the caller surfaces the error via
+                // its own side channel (see `read::parse_content_document`).
+                if item.is_err() {
+                    break;
+                }
+
                 if let Some(elem) = XmlElement::new(iter) {
                     vec.push(elem);
                 }
@@ -86,7 +122,7 @@ pub mod util {
     }
 
     impl XmlElement {
-        pub fn new<R: Read>(iter: &mut Peekable<Events<R>>) -> Option<Self> {
+        pub fn new<I: Iterator<Item = Result<XmlEvent, xml::reader::Error>>>(iter: &mut Peekable<I>) -> Option<Self> {
             let mut children = Vec::new();
 
             match iter.peek()?.as_ref().ok()? {
@@ -185,5 +221,31 @@ pub mod util {
                     .find(|&e| f(e))
             }
         }
+
+        /// Maps every direct child that successfully parses as `T` via [`FromXml`],
+        /// silently skipping children of a different element type.
+        pub fn parse_children<T: FromXml>(&self) -> Vec<T> {
+            self.children.iter()
+                .filter_map(|e| T::from_xml(e).ok())
+                .collect()
+        }
+    }
+
+    /// Scans `iter` for the next `StartElement` named `local_name`, materializing
+    /// only that element (via [`XmlElement::new`]) and discarding everything before
+    /// it one event at a time, without ever building a tree for the skipped
+    /// content. Used by streaming parse paths that only need a handful of elements
+    /// out of a much larger document.
+    pub fn find_by_name<I: Iterator<Item = Result<XmlEvent, xml::reader::Error>>>(iter: &mut Peekable<I>, local_name: &str) -> Option<XmlElement> {
+        loop {
+            match iter.peek()?.as_ref().ok()? {
+                XmlEvent::StartElement { name, .. } if name.local_name == local_name => {
+                    return XmlElement::new(iter);
+                }
+                _ => {
+                    let _ = iter.next();
+                }
+            }
+        }
     }
 }
\ No newline at end of file
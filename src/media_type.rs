@@ -65,12 +65,109 @@ impl TryFrom<&PathBuf> for MediaType {
     }
 }
 
+/// Returns `true` if `bytes` starts with `prefix`.
+fn starts_with(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && &bytes[..prefix.len()] == prefix
+}
+
+/// Returns `true` if `bytes` is an XML document whose root element is
+/// `<svg`, skipping over a leading `<?xml ... ?>` declaration, `<!--
+/// comments -->`, `<!DOCTYPE ...>`, and whitespace. A bare `<?xml ... ?>`
+/// prolog isn't enough on its own: XHTML content documents, OPF package
+/// documents, and NCX files all start with one too.
+fn has_svg_root(bytes: &[u8]) -> bool {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return false,
+    };
+    let mut rest = text.trim_start();
+
+    if rest.starts_with("<?xml") {
+        rest = match rest.find("?>") {
+            Some(end) => rest[end + "?>".len()..].trim_start(),
+            None => return false,
+        };
+    }
+
+    loop {
+        if rest.starts_with("<!--") {
+            rest = match rest.find("-->") {
+                Some(end) => rest[end + "-->".len()..].trim_start(),
+                None => return false,
+            };
+        } else if rest.starts_with("<!") {
+            rest = match rest.find('>') {
+                Some(end) => rest[end + 1..].trim_start(),
+                None => return false,
+            };
+        } else {
+            break;
+        }
+    }
+
+    starts_with(rest.as_bytes(), b"<svg")
+}
+
+impl MediaType {
+    /// Returns `true` if this is one of the EPUB 3 core media types, i.e. a
+    /// type a reading system is required to render natively without relying
+    /// on a manifest `fallback`. `ImageType::WEBP` and `AudioType::OGG` are
+    /// the two variants this crate can represent that aren't in that table;
+    /// every other variant is. Callers that reason about fallback eligibility
+    /// (e.g. [`Manifest::renderable_item`](crate::epub::package_document::Manifest::renderable_item))
+    /// use this instead of hardcoding the core-media-type table themselves.
+    pub fn is_core_media_type(&self) -> bool {
+        match self {
+            MediaType::Image(ImageType::WEBP) => false,
+            MediaType::Audio(AudioType::OGG) => false,
+            MediaType::Image(_) | MediaType::Application(_) | MediaType::Audio(_) | MediaType::Text(_) => true,
+        }
+    }
+
+    /// Sniffs a media type from a resource's leading bytes (the handful of
+    /// magic-byte signatures a reading system is likely to encounter), for
+    /// resources whose declared media type can't be trusted: a mislabeled
+    /// manifest item, or one with no extension at all. Returns a
+    /// [`EPUBError::MediaTypeError`] if none of the known signatures match.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, failure::Error> {
+        if starts_with(bytes, b"GIF87a") || starts_with(bytes, b"GIF89a") {
+            return Ok(MediaType::Image(ImageType::GIF));
+        }
+        if starts_with(bytes, &[0xFF, 0xD8, 0xFF]) {
+            return Ok(MediaType::Image(ImageType::JPEG));
+        }
+        if starts_with(bytes, &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            return Ok(MediaType::Image(ImageType::PNG));
+        }
+        if has_svg_root(bytes) {
+            return Ok(MediaType::Image(ImageType::SVG));
+        }
+        if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            return Ok(MediaType::Image(ImageType::WEBP));
+        }
+        if starts_with(bytes, b"ID3") || starts_with(bytes, &[0xFF, 0xFB]) {
+            return Ok(MediaType::Audio(AudioType::MPEG));
+        }
+        if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+            return Ok(MediaType::Audio(AudioType::MP4));
+        }
+        if starts_with(bytes, b"OggS") {
+            return Ok(MediaType::Audio(AudioType::OGG));
+        }
+
+        Err(EPUBError::MediaTypeError {
+            err_msg: format!("Could not sniff a media type from the leading bytes: {:?}", bytes)
+        }.into())
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum ImageType {
     GIF,
     JPEG,
     PNG,
     SVG,
+    WEBP,
 }
 
 impl FromStr for ImageType {
@@ -82,6 +179,7 @@ impl FromStr for ImageType {
             "jpeg" | "jpg" | "jpe" => Ok(ImageType::JPEG),
             "png" => Ok(ImageType::PNG),
             "svg" | "svgz" => Ok(ImageType::SVG),
+            "webp" => Ok(ImageType::WEBP),
             _ =>
                 Err(EPUBError::MediaTypeError {
                     err_msg: format!("Invalid extension: {}", s)
@@ -97,6 +195,7 @@ impl ToString for ImageType {
             ImageType::JPEG => "jpeg",
             ImageType::PNG => "png",
             ImageType::SVG => "svg+xml",
+            ImageType::WEBP => "webp",
         }.to_string()
     }
 }
@@ -113,6 +212,8 @@ pub enum ApplicationType {
     MediaOverlays,
     /// Text-to-Speech (TTS) 発音語彙
     PLS,
+    /// EPUB 2 NCX navigation document (`toc.ncx`)
+    NCX,
 }
 
 impl FromStr for ApplicationType {
@@ -125,6 +226,7 @@ impl FromStr for ApplicationType {
             "woff" | "woff2" => Ok(ApplicationType::WOFF),
             "smil" => Ok(ApplicationType::MediaOverlays),
             "pls" => Ok(ApplicationType::PLS),
+            "ncx" => Ok(ApplicationType::NCX),
             _ => Err(())
         }
     }
@@ -138,6 +240,7 @@ impl ToString for ApplicationType {
             ApplicationType::WOFF => "font-woff",
             ApplicationType::MediaOverlays => "smil+xml",
             ApplicationType::PLS => "pls+xml",
+            ApplicationType::NCX => "x-dtbncx+xml",
         }.to_string()
     }
 }
@@ -148,6 +251,8 @@ pub enum AudioType {
     MPEG,
     /// MP4 コンテナを使用している AAC LC オーディオ
     MP4,
+    /// Ogg コンテナを使用している Vorbis/Opus オーディオ
+    OGG,
 }
 
 impl FromStr for AudioType {
@@ -157,6 +262,7 @@ impl FromStr for AudioType {
         match s {
             "mp3" => Ok(AudioType::MPEG),
             "aac" | "mp4" => Ok(AudioType::MP4),
+            "ogg" | "oga" => Ok(AudioType::OGG),
             _ =>
                 Err(EPUBError::MediaTypeError {
                     err_msg: format!("Invalid extension: {}", s)
@@ -170,6 +276,7 @@ impl ToString for AudioType {
         match self {
             AudioType::MPEG => "mpeg",
             AudioType::MP4 => "mp4",
+            AudioType::OGG => "ogg",
         }.to_string()
     }
 }
@@ -1,19 +1,102 @@
 use crate::prelude::*;
+use crate::util::Xml;
 
 use std::io::{Read, Seek, BufReader};
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::convert::TryFrom;
 
 use zip::read::{ZipArchive, ZipFile};
 
+use xml::reader::ParserConfig;
+
 use failure::Error;
 use failure::_core::ops::Deref;
 use std::collections::{HashSet, HashMap};
+use std::rc::Rc;
+use std::cell::RefCell;
+
+/// HTML named entities outside of XML's built-in five (`&amp;`, `&lt;`, `&gt;`,
+/// `&apos;`, `&quot;`) that real-world EPUB content documents rely on without
+/// declaring in a DTD. Covers `nbsp` plus the rest of the Latin-1 (ISO 8859-1)
+/// set, since xml-rs otherwise rejects any undeclared entity reference.
+const HTML_LATIN1_ENTITIES: &'static [(&'static str, char)] = &[
+    ("nbsp", '\u{00A0}'), ("iexcl", '\u{00A1}'), ("cent", '\u{00A2}'), ("pound", '\u{00A3}'),
+    ("curren", '\u{00A4}'), ("yen", '\u{00A5}'), ("brvbar", '\u{00A6}'), ("sect", '\u{00A7}'),
+    ("uml", '\u{00A8}'), ("copy", '\u{00A9}'), ("ordf", '\u{00AA}'), ("laquo", '\u{00AB}'),
+    ("not", '\u{00AC}'), ("shy", '\u{00AD}'), ("reg", '\u{00AE}'), ("macr", '\u{00AF}'),
+    ("deg", '\u{00B0}'), ("plusmn", '\u{00B1}'), ("sup2", '\u{00B2}'), ("sup3", '\u{00B3}'),
+    ("acute", '\u{00B4}'), ("micro", '\u{00B5}'), ("para", '\u{00B6}'), ("middot", '\u{00B7}'),
+    ("cedil", '\u{00B8}'), ("sup1", '\u{00B9}'), ("ordm", '\u{00BA}'), ("raquo", '\u{00BB}'),
+    ("frac14", '\u{00BC}'), ("frac12", '\u{00BD}'), ("frac34", '\u{00BE}'), ("iquest", '\u{00BF}'),
+    ("Agrave", '\u{00C0}'), ("Aacute", '\u{00C1}'), ("Acirc", '\u{00C2}'), ("Atilde", '\u{00C3}'), ("Auml", '\u{00C4}'), ("Aring", '\u{00C5}'), ("AElig", '\u{00C6}'), ("Ccedil", '\u{00C7}'),
+    ("Egrave", '\u{00C8}'), ("Eacute", '\u{00C9}'), ("Ecirc", '\u{00CA}'), ("Euml", '\u{00CB}'), ("Igrave", '\u{00CC}'), ("Iacute", '\u{00CD}'), ("Icirc", '\u{00CE}'), ("Iuml", '\u{00CF}'),
+    ("ETH", '\u{00D0}'), ("Ntilde", '\u{00D1}'), ("Ograve", '\u{00D2}'), ("Oacute", '\u{00D3}'), ("Ocirc", '\u{00D4}'), ("Otilde", '\u{00D5}'), ("Ouml", '\u{00D6}'), ("times", '\u{00D7}'),
+    ("Oslash", '\u{00D8}'), ("Ugrave", '\u{00D9}'), ("Uacute", '\u{00DA}'), ("Ucirc", '\u{00DB}'), ("Uuml", '\u{00DC}'), ("Yacute", '\u{00DD}'), ("THORN", '\u{00DE}'), ("szlig", '\u{00DF}'),
+    ("agrave", '\u{00E0}'), ("aacute", '\u{00E1}'), ("acirc", '\u{00E2}'), ("atilde", '\u{00E3}'), ("auml", '\u{00E4}'), ("aring", '\u{00E5}'), ("aelig", '\u{00E6}'), ("ccedil", '\u{00E7}'),
+    ("egrave", '\u{00E8}'), ("eacute", '\u{00E9}'), ("ecirc", '\u{00EA}'), ("euml", '\u{00EB}'), ("igrave", '\u{00EC}'), ("iacute", '\u{00ED}'), ("icirc", '\u{00EE}'), ("iuml", '\u{00EF}'),
+    ("eth", '\u{00F0}'), ("ntilde", '\u{00F1}'), ("ograve", '\u{00F2}'), ("oacute", '\u{00F3}'), ("ocirc", '\u{00F4}'), ("otilde", '\u{00F5}'), ("ouml", '\u{00F6}'), ("divide", '\u{00F7}'),
+    ("oslash", '\u{00F8}'), ("ugrave", '\u{00F9}'), ("uacute", '\u{00FA}'), ("ucirc", '\u{00FB}'), ("uuml", '\u{00FC}'), ("yacute", '\u{00FD}'), ("thorn", '\u{00FE}'), ("yuml", '\u{00FF}'),
+];
+
+/// A [`ParserConfig`] for the markup real-world EPUB content documents actually
+/// contain: it pre-registers the HTML named entities above (which xml-rs would
+/// otherwise reject as undeclared), reports CDATA sections as plain characters,
+/// and drops comments.
+fn content_parser_config() -> ParserConfig {
+    HTML_LATIN1_ENTITIES.iter()
+        .fold(ParserConfig::new(), |config, (name, ch)| config.add_entity(*name, ch.to_string()))
+        .cdata_to_characters(true)
+        .ignore_comments(true)
+}
+
+/// Parses `reader` as a content document, tolerating the markup covered by
+/// [`content_parser_config`]. Unlike building an [`Xml`] tree directly, this
+/// notices an unrecoverable parse error (rather than silently truncating the
+/// tree at the point of failure) and surfaces it as an
+/// [`EPUBError::XMLError`] naming the offending resource.
+fn parse_content_document<T: Read>(resource: &str, reader: T) -> Result<Xml, Error> {
+    let parser = xml::EventReader::new_with_config(reader, content_parser_config());
+
+    let error = Rc::new(RefCell::new(None));
+    let error_sink = error.clone();
+    let mut events = parser.into_iter()
+        .map(move |event| {
+            if let Err(ref e) = event {
+                *error_sink.borrow_mut() = Some(e.clone());
+            }
+            event
+        })
+        .peekable();
+
+    let xml = Xml::new(&mut events);
+
+    let taken = error.borrow_mut().take();
+    match taken {
+        Some(error) => Err(EPUBError::XMLError { resource: resource.to_string(), error }.into()),
+        None => Ok(xml),
+    }
+}
 
 #[derive(Debug)]
 pub struct EPUBReader<R: Read + Seek> {
     archive: ZipArchive<R>,
     pub package_documents: Vec<PackageDocument>,
+    active_package_document_path: Option<PathBuf>,
+}
+
+/// Resolves `.`/`..` segments in a joined path without touching the
+/// filesystem, since zip entry names are just strings, not real paths.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => { out.pop(); }
+            std::path::Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
 }
 
 impl<R: Read + Seek> EPUBReader<R> {
@@ -25,6 +108,9 @@ impl<R: Read + Seek> EPUBReader<R> {
         self.package_documents.get(0)
     }
 
+    /// Raw `href`s as declared in the manifest — relative to the package
+    /// document's own directory, not the zip root. Pass these through
+    /// [`Self::resource_path`] before looking them up in the archive.
     pub fn resources(&self) -> HashSet<PathBuf> {
         if let Some(pd) = self.package_document() {
             pd.manifest.items.iter()
@@ -33,6 +119,9 @@ impl<R: Read + Seek> EPUBReader<R> {
         } else { HashSet::new() }
     }
 
+    /// Raw `href`s of the spine's items, in spine order, as declared in the
+    /// manifest. Like [`Self::resources`], pass these through
+    /// [`Self::resource_path`] before looking them up in the archive.
     pub fn spine_resources(&self) -> Vec<PathBuf> {
         if let Some(pd) = self.package_document() {
             let resources = pd.manifest.items.iter()
@@ -46,6 +135,261 @@ impl<R: Read + Seek> EPUBReader<R> {
                 .collect()
         } else { vec![] }
     }
+
+    /// The directory the active package document lives in, within the zip —
+    /// every `href` in its manifest is relative to this, not the zip root.
+    fn package_document_dir(&self) -> PathBuf {
+        self.active_package_document_path.as_ref()
+            .and_then(|p| p.parent())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default()
+    }
+
+    /// Resolves a manifest `href` against the package document's directory
+    /// and normalizes any `.`/`..` segments, producing the actual in-zip
+    /// entry name `href` refers to.
+    pub fn resource_path(&self, href: &Path) -> PathBuf {
+        normalize_path(&self.package_document_dir().join(href))
+    }
+
+    /// Reads a resource's raw bytes plus its sniffed [`MediaType`], resolving
+    /// `href` against the package document's directory via
+    /// [`Self::resource_path`].
+    pub fn read_resource(&mut self, href: &Path) -> Result<(Vec<u8>, MediaType), Error> {
+        let path = self.resource_path(href);
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+
+        let mut file = self.archive.by_name(name)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let media_type = MediaType::from_bytes(&bytes)
+            .or_else(|_| MediaType::try_from(&path))?;
+
+        Ok((bytes, media_type))
+    }
+
+    /// Classifies a resource by sniffing its leading bytes (see
+    /// [`MediaType::from_bytes`]). When no signature matches (plain-text formats
+    /// like CSS/JS have none), returns the manifest-declared media type, or the
+    /// one implied by the file extension if the manifest declares none. If the
+    /// manifest declares a different media type than what was sniffed, returns
+    /// [`EPUBError::MediaTypeMismatch`] instead, so callers can validate the
+    /// package rather than silently trusting a mislabeled `<item>`.
+    pub fn media_type_of(&mut self, href: &Path) -> Result<MediaType, Error> {
+        let path = self.resource_path(href);
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+
+        let mut file = self.archive.by_name(name)?;
+        let mut buf = [0u8; 16];
+        let n = file.read(&mut buf)?;
+        drop(file);
+        let sniffed = MediaType::from_bytes(&buf[..n]).ok();
+
+        let declared = self.package_document()
+            .and_then(|pd| pd.manifest.items.iter().find(|i| i.href == href.to_str().unwrap_or_default()))
+            .map(|i| i.media_type().clone());
+
+        match (sniffed, declared) {
+            (Some(sniffed), Some(declared)) if sniffed != declared => {
+                Err(EPUBError::MediaTypeMismatch {
+                    resource: name.to_string(),
+                    declared,
+                    detected: sniffed,
+                }.into())
+            }
+            (Some(sniffed), _) => Ok(sniffed),
+            (None, Some(declared)) => Ok(declared),
+            (None, None) => Ok(MediaType::try_from(&path)?),
+        }
+    }
+
+    /// Reads a spine XHTML document and rewrites every `<img src>`, `<link
+    /// href>`, `<script src>`, and CSS `url(...)` reference into a base64
+    /// `data:` URI (see [`crate::inline`]), producing a standalone page that
+    /// renders without the surrounding zip. A resource that fails to resolve
+    /// is left as its original URL rather than aborting the whole document.
+    pub fn inline_document(&mut self, spine_href: &Path) -> Result<String, Error> {
+        let path = self.resource_path(spine_href);
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+
+        let mut file = self.archive.by_name(name)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        drop(file);
+
+        let base_dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let archive = &mut self.archive;
+
+        Ok(crate::inline::inline_document(&content, |href| {
+            if href.starts_with("data:") || href.contains("://") {
+                return None;
+            }
+
+            let resource_path = normalize_path(&base_dir.join(href));
+            let name = resource_path.to_str()?;
+            let mut file = archive.by_name(name).ok()?;
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).ok()?;
+
+            let media_type = MediaType::from_bytes(&bytes)
+                .or_else(|_| MediaType::try_from(&resource_path))
+                .ok()?;
+
+            Some((bytes, media_type))
+        }))
+    }
+
+    /// Returns the ordered Media Overlay clips synchronizing `spine_href`'s
+    /// audio narration, by following its manifest item's `media-overlay`
+    /// attribute to the referenced SMIL document and parsing its `<par>`
+    /// elements. See [`crate::smil`].
+    pub fn media_overlay(&mut self, spine_href: &Path) -> Result<MediaOverlay, Error> {
+        let pd = self.package_document().ok_or(EPUBError::PackageDocumentError {
+            err_msg: "No package document.".to_string(),
+        })?;
+
+        let spine_href_str = spine_href.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Spine resource path is not valid UTF-8: {:?}", spine_href),
+        })?;
+
+        let content_item = pd.manifest.items.iter()
+            .find(|i| i.href == spine_href_str)
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: format!("No manifest item for '{}'.", spine_href_str),
+            })?;
+
+        let overlay_id = content_item.media_overlay_ref().cloned()
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: format!("'{}' has no media-overlay attribute.", spine_href_str),
+            })?;
+
+        let smil_href = pd.manifest.items.iter()
+            .find(|i| i.id == overlay_id)
+            .map(|i| i.href.clone())
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: format!("Media overlay item '{}' not found in manifest.", overlay_id),
+            })?;
+
+        let path = self.resource_path(Path::new(&smil_href));
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+
+        let file = self.archive.by_name(name)?;
+        let xml = parse_content_document(name, BufReader::new(file))?;
+
+        Ok(crate::smil::parse_smil(&xml))
+    }
+
+    /// Resolves the spine's linear items (i.e. excluding `linear="no"` entries)
+    /// against the manifest, per [`PackageDocument::reading_order`], and then
+    /// against the package document's directory via [`Self::resource_path`].
+    pub fn linear_resources(&self) -> Result<Vec<PathBuf>, Error> {
+        let pd = self.package_document().ok_or(EPUBError::PackageDocumentError {
+            err_msg: "No package document.".to_string(),
+        })?;
+        Ok(pd.reading_order()?.into_iter().map(|i| self.resource_path(Path::new(&i.href))).collect())
+    }
+
+    /// Walks the spine's linear items in reading order and reduces each one's
+    /// content document to a [`crate::extract::Chapter`]: plain text plus paragraph-line
+    /// ranges and inline style-attribute runs, for rendering without re-walking
+    /// the original XHTML. See [`crate::extract`].
+    pub fn extract_chapters(&mut self) -> Result<Vec<crate::extract::Chapter>, Error> {
+        self.linear_resources()?.iter()
+            .map(|href| {
+                let name = href.to_str().ok_or(EPUBError::PackageDocumentError {
+                    err_msg: format!("Spine resource path is not valid UTF-8: {:?}", href),
+                })?;
+                let file = self.archive.by_name(name)?;
+                let xml = parse_content_document(name, BufReader::new(file))?;
+                Ok(crate::extract::extract_chapter(&xml))
+            })
+            .collect()
+    }
+
+    /// Walks the spine in reading order and renders each referenced XHTML resource
+    /// to plain text, giving callers a one-call "book as text" API.
+    pub fn chapters(&mut self) -> Result<Vec<String>, Error> {
+        self.spine_resources().iter()
+            .map(|href| {
+                let path = self.resource_path(href);
+                let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+                    err_msg: format!("Spine resource path is not valid UTF-8: {:?}", path),
+                })?;
+                let file = self.archive.by_name(name)?;
+                let xml = parse_content_document(name, BufReader::new(file))?;
+                Ok(crate::render::render_text(&xml))
+            })
+            .collect()
+    }
+
+    /// Returns the book's table of contents as a tree of [`TocEntry`]s, parsed
+    /// from the EPUB 3 navigation document (the manifest item with
+    /// `properties="nav"`) if one is present, falling back to the EPUB 2
+    /// `toc.ncx` referenced by `Spine::toc` (or by its `application/x-dtbncx+xml`
+    /// media type) otherwise — so callers get one navigation API regardless of
+    /// which version of EPUB a book uses.
+    pub fn toc(&mut self) -> Result<Vec<TocEntry>, Error> {
+        self.toc_from_nav().or_else(|_| self.toc_from_ncx())
+    }
+
+    fn toc_from_nav(&mut self) -> Result<Vec<TocEntry>, Error> {
+        let nav_href = self.package_document()
+            .map(|pd| pd.manifest.nav.href.clone())
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "No package document.".to_string(),
+            })?;
+
+        let path = self.resource_path(Path::new(&nav_href));
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+        let base_dir = crate::toc::parent_dir(name);
+
+        let file = self.archive.by_name(name)?;
+        let xml = parse_content_document(name, BufReader::new(file))?;
+
+        crate::toc::parse_nav_xhtml(&xml, &base_dir)
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "No <nav epub:type=\"toc\"> element found in the navigation document.".to_string(),
+            }.into())
+    }
+
+    fn toc_from_ncx(&mut self) -> Result<Vec<TocEntry>, Error> {
+        let pd = self.package_document().ok_or(EPUBError::PackageDocumentError {
+            err_msg: "No package document.".to_string(),
+        })?;
+
+        let ncx_href = pd.spine.toc.as_ref()
+            .and_then(|id| pd.manifest.items.iter().find(|i| &i.id == id))
+            .or_else(|| pd.manifest.items.iter().find(|i| i.media_type().to_string() == "application/x-dtbncx+xml"))
+            .map(|i| i.href.clone())
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "No NCX document referenced by the spine or manifest.".to_string(),
+            })?;
+
+        let path = self.resource_path(Path::new(&ncx_href));
+        let name = path.to_str().ok_or(EPUBError::PackageDocumentError {
+            err_msg: format!("Resource path is not valid UTF-8: {:?}", path),
+        })?;
+        let base_dir = crate::toc::parent_dir(name);
+
+        let file = self.archive.by_name(name)?;
+        let xml = parse_content_document(name, BufReader::new(file))?;
+
+        crate::toc::parse_ncx(&xml, &base_dir)
+            .ok_or(EPUBError::PackageDocumentError {
+                err_msg: "No <navMap> element found in the NCX document.".to_string(),
+            }.into())
+    }
 }
 
 impl EPUBReader<BufReader<File>> {
@@ -59,11 +403,12 @@ impl EPUBReader<BufReader<File>> {
         let mut reader = Self {
             archive,
             package_documents: Vec::new(),
+            active_package_document_path: None,
         };
 
         let package_documents_paths = reader.package_document_paths()?;
         let packages = package_documents_paths
-            .into_iter()
+            .iter()
             .filter_map(|p| {
                 let package_document_file = reader.archive.by_name(p.to_str()?).ok()?;
                 PackageDocument::new(package_document_file).ok()
@@ -72,6 +417,40 @@ impl EPUBReader<BufReader<File>> {
 
         Ok(Self {
             package_documents: packages,
+            active_package_document_path: package_documents_paths.into_iter().next(),
+            ..reader
+        })
+    }
+
+    /// Like [`EPUBReader::new`], but parses each package document with
+    /// [`PackageDocument::new_streaming`] instead of materializing its full XML
+    /// tree, for large package documents where that tree would otherwise dominate
+    /// memory use.
+    pub fn new_streaming<P>(path: P) -> Result<Self, Error> where P: AsRef<Path> {
+        let archive: ZipArchive<BufReader<File>> = std::fs::File::open(path)
+            .map(|file| {
+                let buf_reader = std::io::BufReader::new(file);
+                ZipArchive::new(buf_reader)
+            })??;
+
+        let mut reader = Self {
+            archive,
+            package_documents: Vec::new(),
+            active_package_document_path: None,
+        };
+
+        let package_documents_paths = reader.package_document_paths()?;
+        let packages = package_documents_paths
+            .iter()
+            .filter_map(|p| {
+                let package_document_file = reader.archive.by_name(p.to_str()?).ok()?;
+                PackageDocument::new_streaming(package_document_file).ok()
+            })
+            .collect::<Vec<PackageDocument>>();
+
+        Ok(Self {
+            package_documents: packages,
+            active_package_document_path: package_documents_paths.into_iter().next(),
             ..reader
         })
     }
@@ -0,0 +1,40 @@
+use crate::util::{Xml, XmlElement};
+use xml::reader::XmlEvent;
+
+/// Elements whose entire subtree is dropped when extracting plain text.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "svg", "nav", "iframe", "head"];
+
+/// Elements that introduce a line break in the rendered plain text.
+const BLOCK_ELEMENTS: &[&str] = &["p", "div", "br", "li", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Walks a parsed XHTML content document and renders it to readable plain text:
+/// text nodes are emitted in document order, `script`/`style`/`svg`/`nav`/`iframe`/`head`
+/// subtrees are skipped entirely, and block-level elements insert a newline.
+pub fn render_text(xml: &Xml) -> String {
+    let mut text = String::new();
+    for elem in xml.iter() {
+        render_element(elem, &mut text);
+    }
+    text
+}
+
+fn render_element(elem: &XmlElement, text: &mut String) {
+    match &elem.event {
+        XmlEvent::StartElement { name, .. } if SKIPPED_ELEMENTS.contains(&name.local_name.as_str()) => {}
+        XmlEvent::Characters(s) | XmlEvent::CData(s) => text.push_str(s),
+        XmlEvent::StartElement { name, .. } => {
+            for child in &elem.children {
+                render_element(child, text);
+            }
+            if BLOCK_ELEMENTS.contains(&name.local_name.as_str()) {
+                text.push('\n');
+            }
+        }
+        XmlEvent::StartDocument { .. } => {
+            for child in &elem.children {
+                render_element(child, text);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use xml::reader::XmlEvent;
+
+use crate::util::{Xml, XmlElement};
+
+/// One `<par>` of a SMIL Media Overlay document: a text fragment paired with
+/// the span of an audio resource that narrates it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub text_src: String,
+    pub audio_src: String,
+    pub clip_begin: Duration,
+    pub clip_end: Duration,
+}
+
+/// The ordered clips synchronizing a content document's text with its audio
+/// narration, parsed from a SMIL Media Overlay document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaOverlay {
+    pub clips: Vec<Clip>,
+}
+
+/// Parses a SMIL Media Overlay document's `<par>` elements — each pairing a
+/// `<text src="chapter.xhtml#id"/>` with an `<audio src="..." clipBegin="..."
+/// clipEnd="..."/>` — into an ordered [`MediaOverlay`].
+pub fn parse_smil(xml: &Xml) -> MediaOverlay {
+    let mut clips = Vec::new();
+    for elem in xml.iter() {
+        collect_pars(elem, &mut clips);
+    }
+    MediaOverlay { clips }
+}
+
+fn collect_pars(elem: &XmlElement, clips: &mut Vec<Clip>) {
+    if is_element(elem, "par") {
+        if let Some(clip) = parse_par(elem) {
+            clips.push(clip);
+        }
+    }
+    for child in &elem.children {
+        collect_pars(child, clips);
+    }
+}
+
+fn parse_par(par: &XmlElement) -> Option<Clip> {
+    let text = find_child(par, "text")?;
+    let text_src = attr(text, "src")?;
+
+    let audio = find_child(par, "audio")?;
+    let audio_src = attr(audio, "src")?;
+    let clip_begin = attr(audio, "clipBegin").map(|s| parse_clock(&s)).unwrap_or_default();
+    let clip_end = attr(audio, "clipEnd").map(|s| parse_clock(&s)).unwrap_or_default();
+
+    Some(Clip { text_src, audio_src, clip_begin, clip_end })
+}
+
+/// Parses a SMIL clock value in any of its three permitted forms: `SS.mmm`,
+/// `HH:MM:SS.mmm`, or an `npt=SS.mmm` Normal Play Time value. Unparseable
+/// components default to zero rather than failing the whole document.
+fn parse_clock(raw: &str) -> Duration {
+    let s = raw.trim().trim_start_matches("npt=");
+    let parts: Vec<&str> = s.split(':').collect();
+
+    let seconds: f64 = match parts.as_slice() {
+        [h, m, sec] => {
+            h.parse().unwrap_or(0.0) * 3600.0
+                + m.parse().unwrap_or(0.0) * 60.0
+                + sec.parse().unwrap_or(0.0)
+        }
+        [sec] => sec.parse().unwrap_or(0.0),
+        _ => 0.0,
+    };
+
+    let seconds = if seconds.is_finite() { seconds.max(0.0) } else { 0.0 };
+    Duration::from_secs_f64(seconds)
+}
+
+fn find_child<'a>(elem: &'a XmlElement, name: &str) -> Option<&'a XmlElement> {
+    elem.children.iter().find(|e| is_element(e, name))
+}
+
+fn is_element(elem: &XmlElement, name: &str) -> bool {
+    match &elem.event {
+        XmlEvent::StartElement { name: n, .. } => n.local_name == name,
+        _ => false,
+    }
+}
+
+fn attr(elem: &XmlElement, key: &str) -> Option<String> {
+    match &elem.event {
+        XmlEvent::StartElement { attributes, .. } => attributes.iter()
+            .find(|a| a.name.local_name == key)
+            .map(|a| a.value.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> Xml {
+        let parser = xml::EventReader::new(std::io::Cursor::new(xml.as_bytes().to_vec()));
+        Xml::new(&mut parser.into_iter().peekable())
+    }
+
+    #[test]
+    fn parses_pars_into_ordered_clips() {
+        let xml = parse(r#"<smil xmlns="http://www.w3.org/ns/SMIL">
+<body>
+<seq>
+<par><text src="ch01.xhtml#s1"/><audio src="ch01.mp3" clipBegin="0:00:01.000" clipEnd="0:00:02.500"/></par>
+<par><text src="ch01.xhtml#s2"/><audio src="ch01.mp3" clipBegin="2.500" clipEnd="5.000"/></par>
+</seq>
+</body>
+</smil>"#);
+
+        let overlay = parse_smil(&xml);
+
+        assert_eq!(overlay.clips.len(), 2);
+        assert_eq!(overlay.clips[0].text_src, "ch01.xhtml#s1");
+        assert_eq!(overlay.clips[0].audio_src, "ch01.mp3");
+        assert_eq!(overlay.clips[0].clip_begin, Duration::from_secs_f64(1.0));
+        assert_eq!(overlay.clips[0].clip_end, Duration::from_secs_f64(2.5));
+
+        assert_eq!(overlay.clips[1].clip_begin, Duration::from_secs_f64(2.5));
+        assert_eq!(overlay.clips[1].clip_end, Duration::from_secs_f64(5.0));
+    }
+
+    #[test]
+    fn par_missing_a_text_or_audio_child_is_skipped() {
+        let xml = parse(r#"<smil><body><par><text src="ch01.xhtml#s1"/></par></body></smil>"#);
+        assert_eq!(parse_smil(&xml).clips, Vec::new());
+    }
+
+    #[test]
+    fn parse_clock_defaults_unparseable_components_to_zero() {
+        assert_eq!(parse_clock("npt=1.5"), Duration::from_secs_f64(1.5));
+        assert_eq!(parse_clock("garbage"), Duration::from_secs(0));
+        assert_eq!(parse_clock("00:00:03.250"), Duration::from_secs_f64(3.25));
+    }
+}
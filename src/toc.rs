@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+
+use xml::reader::XmlEvent;
+
+use crate::util::{Xml, XmlElement};
+
+/// One entry of a book's table of contents, recursively nesting sub-entries.
+/// Produced uniformly by [`parse_nav_xhtml`] (EPUB 3 navigation documents) and
+/// [`parse_ncx`] (EPUB 2 `toc.ncx`), so callers get one navigation API
+/// regardless of which version of EPUB a book uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub title: String,
+    pub href: String,
+    pub fragment: Option<String>,
+    pub children: Vec<TocEntry>,
+}
+
+/// Parses the EPUB 3 `<nav epub:type="toc">` structure of a navigation document
+/// into a tree of [`TocEntry`]s, resolving each `<a href>` against `base_dir` (the
+/// directory of the navigation document itself) so callers get zip-usable paths.
+pub fn parse_nav_xhtml(xml: &Xml, base_dir: &Path) -> Option<Vec<TocEntry>> {
+    let toc_nav = xml.iter().find_map(find_toc_nav)?;
+    let ol = find_child(toc_nav, "ol")?;
+
+    Some(parse_ol(ol, base_dir))
+}
+
+fn find_toc_nav(elem: &XmlElement) -> Option<&XmlElement> {
+    if is_element(elem, "nav") && attr(elem, "type").as_deref() == Some("toc") {
+        return Some(elem);
+    }
+    elem.children.iter().find_map(find_toc_nav)
+}
+
+fn parse_ol(ol: &XmlElement, base_dir: &Path) -> Vec<TocEntry> {
+    ol.children.iter()
+        .filter(|e| is_element(e, "li"))
+        .filter_map(|li| parse_li(li, base_dir))
+        .collect()
+}
+
+fn parse_li(li: &XmlElement, base_dir: &Path) -> Option<TocEntry> {
+    let a = find_child(li, "a")?;
+    let src = attr(a, "href")?;
+    let label = a.inner_text().trim().to_string();
+    let children = find_child(li, "ol")
+        .map(|ol| parse_ol(ol, base_dir))
+        .unwrap_or_default();
+
+    let (href, fragment) = split_fragment(&src);
+
+    Some(TocEntry {
+        title: label,
+        href: resolve_href(base_dir, &href),
+        fragment,
+        children,
+    })
+}
+
+/// Parses the EPUB 2 `<navMap>` of an NCX document (`toc.ncx`) into the same
+/// [`TocEntry`] tree [`parse_nav_xhtml`] produces from an EPUB 3 navigation
+/// document, resolving each `<content src>` against `base_dir` (the NCX's own
+/// directory). Nested `<navPoint>` elements become nested `TocEntry::children`;
+/// `playOrder` is not consulted since document order already reflects it.
+pub fn parse_ncx(xml: &Xml, base_dir: &Path) -> Option<Vec<TocEntry>> {
+    let nav_map = xml.iter().find_map(find_nav_map)?;
+
+    Some(parse_nav_points(nav_map, base_dir))
+}
+
+fn find_nav_map(elem: &XmlElement) -> Option<&XmlElement> {
+    if is_element(elem, "navMap") {
+        return Some(elem);
+    }
+    elem.children.iter().find_map(find_nav_map)
+}
+
+fn parse_nav_points(parent: &XmlElement, base_dir: &Path) -> Vec<TocEntry> {
+    parent.children.iter()
+        .filter(|e| is_element(e, "navPoint"))
+        .filter_map(|nav_point| parse_nav_point(nav_point, base_dir))
+        .collect()
+}
+
+fn parse_nav_point(nav_point: &XmlElement, base_dir: &Path) -> Option<TocEntry> {
+    let nav_label = find_child(nav_point, "navLabel")?;
+    let text = find_child(nav_label, "text")?;
+    let title = text.inner_text().trim().to_string();
+
+    let content = find_child(nav_point, "content")?;
+    let src = attr(content, "src")?;
+    let (href, fragment) = split_fragment(&src);
+
+    let children = parse_nav_points(nav_point, base_dir);
+
+    Some(TocEntry {
+        title,
+        href: resolve_href(base_dir, &href),
+        fragment,
+        children,
+    })
+}
+
+fn split_fragment(src: &str) -> (String, Option<String>) {
+    match src.find('#') {
+        Some(i) => (src[..i].to_string(), Some(src[i + 1..].to_string())),
+        None => (src.to_string(), None),
+    }
+}
+
+fn resolve_href(base_dir: &Path, href: &str) -> String {
+    crate::read::normalize_path(&base_dir.join(href)).to_string_lossy().into_owned()
+}
+
+fn find_child<'a>(elem: &'a XmlElement, name: &str) -> Option<&'a XmlElement> {
+    elem.children.iter().find(|e| is_element(e, name))
+}
+
+fn is_element(elem: &XmlElement, name: &str) -> bool {
+    match &elem.event {
+        XmlEvent::StartElement { name: n, .. } => n.local_name == name,
+        _ => false,
+    }
+}
+
+fn attr(elem: &XmlElement, key: &str) -> Option<String> {
+    match &elem.event {
+        XmlEvent::StartElement { attributes, .. } => attributes.iter()
+            .find(|a| a.name.local_name == key)
+            .map(|a| a.value.clone()),
+        _ => None,
+    }
+}
+
+/// The directory part of `href` (empty if it has none), for use as the
+/// `base_dir` of [`parse_nav_xhtml`] or [`parse_ncx`].
+pub fn parent_dir(href: &str) -> PathBuf {
+    Path::new(href).parent().unwrap_or_else(|| Path::new("")).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(xml: &str) -> Xml {
+        let parser = xml::EventReader::new(std::io::Cursor::new(xml.as_bytes().to_vec()));
+        Xml::new(&mut parser.into_iter().peekable())
+    }
+
+    #[test]
+    fn parses_nav_xhtml_into_a_toc_tree() {
+        let xml = parse(r#"<html xmlns:epub="http://www.idpf.org/2007/ops">
+<body>
+<nav epub:type="toc"><ol>
+<li><a href="cover.xhtml">Cover</a></li>
+<li><a href="ch01.xhtml#start">Chapter 1</a><ol>
+<li><a href="ch01.xhtml#s1">Section 1</a></li>
+</ol></li>
+</ol></nav>
+</body>
+</html>"#);
+
+        let toc = parse_nav_xhtml(&xml, Path::new("text")).expect("nav toc");
+
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Cover");
+        assert_eq!(toc[0].href, "text/cover.xhtml");
+        assert_eq!(toc[0].fragment, None);
+
+        assert_eq!(toc[1].title, "Chapter 1");
+        assert_eq!(toc[1].href, "text/ch01.xhtml");
+        assert_eq!(toc[1].fragment, Some("start".to_string()));
+        assert_eq!(toc[1].children.len(), 1);
+        assert_eq!(toc[1].children[0].title, "Section 1");
+        assert_eq!(toc[1].children[0].fragment, Some("s1".to_string()));
+    }
+
+    #[test]
+    fn parse_nav_xhtml_returns_none_without_a_toc_nav() {
+        let xml = parse(r#"<html><body><nav epub:type="landmarks"><ol></ol></nav></body></html>"#);
+        assert_eq!(parse_nav_xhtml(&xml, Path::new("")), None);
+    }
+
+    #[test]
+    fn resolve_href_collapses_parent_dir_segments() {
+        let xml = parse(r#"<html xmlns:epub="http://www.idpf.org/2007/ops">
+<body>
+<nav epub:type="toc"><ol>
+<li><a href="../text/ch01.xhtml">Chapter 1</a></li>
+</ol></nav>
+</body>
+</html>"#);
+
+        let toc = parse_nav_xhtml(&xml, Path::new("nav")).expect("nav toc");
+
+        assert_eq!(toc[0].href, "text/ch01.xhtml");
+    }
+
+    #[test]
+    fn split_fragment_separates_href_and_fragment() {
+        assert_eq!(split_fragment("ch01.xhtml#s1"), ("ch01.xhtml".to_string(), Some("s1".to_string())));
+        assert_eq!(split_fragment("ch01.xhtml"), ("ch01.xhtml".to_string(), None));
+    }
+
+    #[test]
+    fn parses_ncx_into_a_toc_tree() {
+        let xml = parse(r#"<ncx>
+<navMap>
+<navPoint><navLabel><text>Cover</text></navLabel><content src="cover.xhtml"/></navPoint>
+<navPoint><navLabel><text>Chapter 1</text></navLabel><content src="ch01.xhtml#start"/>
+<navPoint><navLabel><text>Section 1</text></navLabel><content src="ch01.xhtml#s1"/></navPoint>
+</navPoint>
+</navMap>
+</ncx>"#);
+
+        let toc = parse_ncx(&xml, Path::new("text")).expect("ncx toc");
+
+        assert_eq!(toc.len(), 2);
+        assert_eq!(toc[0].title, "Cover");
+        assert_eq!(toc[0].href, "text/cover.xhtml");
+
+        assert_eq!(toc[1].title, "Chapter 1");
+        assert_eq!(toc[1].fragment, Some("start".to_string()));
+        assert_eq!(toc[1].children.len(), 1);
+        assert_eq!(toc[1].children[0].title, "Section 1");
+    }
+
+    #[test]
+    fn parse_ncx_returns_none_without_a_nav_map() {
+        let xml = parse("<ncx></ncx>");
+        assert_eq!(parse_ncx(&xml, Path::new("")), None);
+    }
+}
@@ -0,0 +1,302 @@
+use std::io::{Seek, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+use zip::write::{FileOptions, ZipWriter};
+
+use crate::EPUBError;
+use crate::media_type::MediaType;
+use crate::epub::package_document::escape_xml as escape;
+
+/// A single chapter to add to an [`EPUBWriter`]: a title (used in the nav
+/// document and the chapter's `<title>`) paired with its XHTML body content.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub body: String,
+}
+
+/// A non-chapter resource (image, stylesheet, font, etc.) to add to an
+/// [`EPUBWriter`], written to the archive at `path` and declared in the
+/// manifest with `media_type`.
+#[derive(Debug, Clone)]
+pub struct Resource {
+    pub path: String,
+    pub media_type: MediaType,
+    pub bytes: Vec<u8>,
+}
+
+/// Builds a spec-valid EPUB 3 container from user-supplied metadata and
+/// chapters: a stored `mimetype` entry, `META-INF/container.xml`, a generated
+/// package document, and a generated `nav.xhtml`.
+#[derive(Debug, Clone)]
+pub struct EPUBWriter {
+    identifier: String,
+    title: String,
+    language: String,
+    creators: Vec<String>,
+    chapters: Vec<Chapter>,
+    resources: Vec<Resource>,
+}
+
+impl EPUBWriter {
+    pub fn new(identifier: impl Into<String>, title: impl Into<String>, language: impl Into<String>) -> Self {
+        Self {
+            identifier: identifier.into(),
+            title: title.into(),
+            language: language.into(),
+            creators: Vec::new(),
+            chapters: Vec::new(),
+            resources: Vec::new(),
+        }
+    }
+
+    pub fn creator(mut self, creator: impl Into<String>) -> Self {
+        self.creators.push(creator.into());
+        self
+    }
+
+    pub fn chapter(mut self, title: impl Into<String>, body: impl Into<String>) -> Self {
+        self.chapters.push(Chapter { title: title.into(), body: body.into() });
+        self
+    }
+
+    /// Adds a binary resource (image, stylesheet, font, etc.) at `path` in the
+    /// archive, declared in the manifest with `media_type`. Unlike chapters,
+    /// resources aren't added to the spine or nav document.
+    pub fn resource(mut self, path: impl Into<String>, media_type: MediaType, bytes: impl Into<Vec<u8>>) -> Self {
+        self.resources.push(Resource { path: path.into(), media_type, bytes: bytes.into() });
+        self
+    }
+
+    /// Serializes the book to `sink` as a zip archive.
+    pub fn write<W: Write + Seek>(&self, sink: W) -> Result<(), Error> {
+        if self.chapters.is_empty() {
+            return Err(EPUBError::PackageDocumentError {
+                err_msg: "An EPUB needs at least one chapter.".to_string(),
+            }.into());
+        }
+
+        let mut zip = ZipWriter::new(sink);
+
+        let stored = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+
+        let deflated = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        zip.start_file("META-INF/container.xml", deflated)?;
+        zip.write_all(Self::container_xml().as_bytes())?;
+
+        let chapter_ids: Vec<String> = (0..self.chapters.len())
+            .map(|i| format!("chapter{}", i + 1))
+            .collect();
+
+        for (id, chapter) in chapter_ids.iter().zip(&self.chapters) {
+            zip.start_file(format!("{}.xhtml", id), deflated)?;
+            zip.write_all(Self::chapter_xhtml(&chapter.title, &chapter.body).as_bytes())?;
+        }
+
+        zip.start_file("nav.xhtml", deflated)?;
+        zip.write_all(self.nav_xhtml(&chapter_ids).as_bytes())?;
+
+        let resource_ids: Vec<String> = (0..self.resources.len())
+            .map(|i| format!("resource{}", i + 1))
+            .collect();
+
+        for resource in &self.resources {
+            zip.start_file(&resource.path, deflated)?;
+            zip.write_all(&resource.bytes)?;
+        }
+
+        zip.start_file("package.opf", deflated)?;
+        zip.write_all(self.package_opf(&chapter_ids, &resource_ids).as_bytes())?;
+
+        zip.finish()?;
+
+        Ok(())
+    }
+
+    fn container_xml() -> String {
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="package.opf" media-type="application/oebps-package+xml"/>
+    </rootfiles>
+</container>"#.to_string()
+    }
+
+    fn chapter_xhtml(title: &str, body: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>{title}</title></head>
+<body>{body}</body>
+</html>"#,
+            title = escape(title),
+            body = body,
+        )
+    }
+
+    fn nav_xhtml(&self, chapter_ids: &[String]) -> String {
+        let items: String = chapter_ids.iter().zip(&self.chapters)
+            .map(|(id, chapter)| format!(r#"<li><a href="{}.xhtml">{}</a></li>"#, id, escape(&chapter.title)))
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+<nav epub:type="toc"><ol>{items}</ol></nav>
+</body>
+</html>"#,
+            title = escape(&self.title),
+            items = items,
+        )
+    }
+
+    /// The current UTC time as a `CCYY-MM-DDThh:mm:ssZ` string, the format EPUB 3
+    /// requires for a package document's `dcterms:modified` meta. Computed from
+    /// [`SystemTime`] by hand (no `chrono` dependency) using the days-since-epoch
+    /// to civil-date algorithm from Howard Hinnant's `chrono::civil_from_days`.
+    fn modified_timestamp() -> String {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let (days, time_of_day) = (secs / 86_400, secs % 86_400);
+        let (hour, minute, second) = (time_of_day / 3600, (time_of_day / 60) % 60, time_of_day % 60);
+
+        let z = days as i64 + 719_468;
+        let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+        let doe = (z - era * 146_097) as u64;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let y = yoe as i64 + era * 400;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = if month <= 2 { y + 1 } else { y };
+
+        format!(
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z",
+            year = year, month = month, day = day, hour = hour, minute = minute, second = second,
+        )
+    }
+
+    fn package_opf(&self, chapter_ids: &[String], resource_ids: &[String]) -> String {
+        let creators: String = self.creators.iter()
+            .map(|c| format!("<dc:creator>{}</dc:creator>", escape(c)))
+            .collect();
+
+        let manifest_items: String = chapter_ids.iter()
+            .map(|id| format!(r#"<item id="{id}" href="{id}.xhtml" media-type="application/xhtml+xml"/>"#, id = id))
+            .collect();
+
+        let resource_items: String = resource_ids.iter().zip(&self.resources)
+            .map(|(id, resource)| format!(
+                r#"<item id="{id}" href="{href}" media-type="{media_type}"/>"#,
+                id = id, href = escape(&resource.path), media_type = resource.media_type.to_string(),
+            ))
+            .collect();
+
+        let spine_items: String = chapter_ids.iter()
+            .map(|id| format!(r#"<itemref idref="{}"/>"#, id))
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="bookid">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+        <dc:identifier id="bookid">{identifier}</dc:identifier>
+        <dc:title>{title}</dc:title>
+        <dc:language>{language}</dc:language>
+        {creators}
+        <meta property="dcterms:modified">{modified}</meta>
+    </metadata>
+    <manifest>
+        <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+        {manifest_items}
+        {resource_items}
+    </manifest>
+    <spine>
+        {spine_items}
+    </spine>
+</package>"#,
+            identifier = escape(&self.identifier),
+            title = escape(&self.title),
+            language = escape(&self.language),
+            creators = creators,
+            modified = Self::modified_timestamp(),
+            manifest_items = manifest_items,
+            resource_items = resource_items,
+            spine_items = spine_items,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+    use crate::media_type::{ImageType, MediaType};
+
+    fn write_to_buffer(writer: &EPUBWriter) -> Result<Vec<u8>, Error> {
+        let mut buf = Cursor::new(Vec::new());
+        writer.write(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    #[test]
+    fn write_requires_a_chapter() {
+        let writer = EPUBWriter::new("urn:uuid:test", "Untitled", "en");
+        assert!(write_to_buffer(&writer).is_err());
+    }
+
+    #[test]
+    fn resource_is_written_and_declared_in_the_manifest() -> Result<(), Error> {
+        let writer = EPUBWriter::new("urn:uuid:test", "Test Book", "en")
+            .chapter("Chapter 1", "<p>Hello</p>")
+            .resource("images/cover.png", MediaType::Image(ImageType::PNG), vec![0x89, b'P', b'N', b'G']);
+
+        let bytes = write_to_buffer(&writer)?;
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+        let mut resource = archive.by_name("images/cover.png")?;
+        let mut content = Vec::new();
+        resource.read_to_end(&mut content)?;
+        assert_eq!(content, vec![0x89, b'P', b'N', b'G']);
+        drop(resource);
+
+        let mut package_opf = String::new();
+        archive.by_name("package.opf")?.read_to_string(&mut package_opf)?;
+        assert!(package_opf.contains(r#"href="images/cover.png" media-type="image/png""#));
+
+        Ok(())
+    }
+
+    /// EPUB 3 requires every package document's `<metadata>` to declare a
+    /// `dcterms:modified` meta in `CCYY-MM-DDThh:mm:ssZ` form.
+    #[test]
+    fn package_opf_declares_dcterms_modified() -> Result<(), Error> {
+        let writer = EPUBWriter::new("urn:uuid:test", "Test Book", "en")
+            .chapter("Chapter 1", "<p>Hello</p>");
+
+        let bytes = write_to_buffer(&writer)?;
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+        let mut package_opf = String::new();
+        archive.by_name("package.opf")?.read_to_string(&mut package_opf)?;
+
+        let modified = package_opf
+            .split(r#"<meta property="dcterms:modified">"#).nth(1)
+            .and_then(|rest| rest.split("</meta>").next())
+            .expect("dcterms:modified meta present");
+
+        assert_eq!(modified.len(), "CCYY-MM-DDThh:mm:ssZ".len());
+        assert!(modified.ends_with('Z'));
+
+        Ok(())
+    }
+}